@@ -1,23 +1,184 @@
+import { createHash } from "node:crypto";
+import { readFileSync } from "node:fs";
+import { isBuiltin } from "node:module";
+import path from "node:path";
 import createCacheKeyFunction from "@jest/create-cache-key-function";
-import type { SyncTransformer, TransformedSource, TransformerFactory } from "@jest/transform";
+import type { SyncTransformer, TransformedSource, TransformerFactory, TransformOptions } from "@jest/transform";
+import JestResolver from "jest-resolve";
 import packageJson from "../package.json";
-import { transform } from "../sys/index.js";
+import { type Diagnostic, transform } from "../sys/index.js";
+
+export type { Diagnostic };
 
 const oxjestVersion = packageJson.version;
 const dumpCodeEnabled = !!process.env.OXJEST_DUMP_CODE;
 
-const factory: TransformerFactory<SyncTransformer> = {
-  createTransformer(): SyncTransformer {
-    const getCacheKey = createCacheKeyFunction(
+export interface OxjestOptions {
+  /**
+   * Whether to keep the live bindings of the imports converted into dynamic imports.
+   * @default true
+   */
+  liveBindings?: boolean;
+  /**
+   * Whether to convert all imports into dynamic imports in modules with hoisted mocks.
+   * By default, only the imports that may reach the mocked modules are converted.
+   * @default false
+   */
+  convertAllImports?: boolean;
+  /**
+   * Whether to fail on the warnings as well as the errors, such as mocks that can't be converted.
+   * By default, the warnings are printed to the console.
+   * @default false
+   */
+  strict?: boolean;
+  /**
+   * Language of the modules with extensions unknown to oxjest, such as `.es6`, as an extension such as `"js"` or `"ts"`.
+   * The languages of the other modules are inferred from their extensions.
+   */
+  sourceType?: string;
+}
+
+/**
+ * Error thrown when a module fails to load or transform, carrying the diagnostics with their locations.
+ */
+export interface TransformError extends Error {
+  diagnostics: Diagnostic[];
+}
+
+/**
+ * Checks that the error is thrown by oxjest with the diagnostics, such as in Jest reporters.
+ */
+export function isTransformError(error: unknown): error is TransformError {
+  return error instanceof Error && Array.isArray((error as Partial<TransformError>).diagnostics);
+}
+
+type Resolver = (specifier: string, from: string) => string;
+
+/**
+ * Creates a resolver that maps the specifiers with `moduleNameMapper`, then resolves them as Jest does.
+ */
+function createResolver(config: TransformOptions["config"]): Resolver {
+  const moduleNameMapper = config.moduleNameMapper.map(
+    ([pattern, replacement]) => [new RegExp(pattern), [replacement].flat()] as const,
+  );
+  const findNodeModule = (specifier: string, from: string) =>
+    JestResolver.findNodeModule(specifier, {
+      basedir: path.dirname(from),
+      conditions: ["import", "default"],
+      extensions: config.moduleFileExtensions.map((extension) => `.${extension}`),
+      moduleDirectory: config.moduleDirectories,
+      paths: config.modulePaths,
+      resolver: config.resolver,
+      rootDir: config.rootDir,
+    });
+
+  return (specifier, from) => {
+    if (isBuiltin(specifier)) {
+      return specifier;
+    }
+
+    const mapper = moduleNameMapper.find(([regex]) => regex.test(specifier));
+    if (!mapper) {
+      const resolved = findNodeModule(specifier, from);
+      if (!resolved) {
+        throw new Error(`Cannot resolve ${specifier} from ${from}`);
+      }
+
+      return resolved;
+    }
+
+    const [regex, replacements] = mapper;
+    for (const replacement of replacements) {
+      const resolved = findNodeModule(specifier.replace(regex, replacement), from);
+      if (resolved) {
+        return resolved;
+      }
+    }
+
+    throw new Error(`Cannot resolve ${specifier} mapped with ${regex}`);
+  };
+}
+
+/**
+ * Checks that the module may have mocks or `require` calls, whose transformation depends on the imported modules.
+ */
+function mayDependOnImports(sourceText: string): boolean {
+  return /\b(?:mock|unstable_mockModule|unmock|require|requireActual)\s*\(/.test(sourceText);
+}
+
+/**
+ * Hashes the paths and the contents of the modules, on which the transformed code depends.
+ */
+function hashDependencies(dependencies: string[]): string {
+  const hash = createHash("sha256");
+  for (const dependency of dependencies) {
+    hash.update(dependency).update("\0");
+    try {
+      hash.update(readFileSync(dependency));
+    } catch {
+      // Modules removed since the transformation change the key as well
+    }
+    hash.update("\0");
+  }
+
+  return hash.digest("hex");
+}
+
+const factory: TransformerFactory<SyncTransformer<OxjestOptions>> = {
+  createTransformer(options?: OxjestOptions): SyncTransformer<OxjestOptions> {
+    const getBaseCacheKey = createCacheKeyFunction(
       ["package.json", "tsconfig.json"],
-      [oxjestVersion, dumpCodeEnabled ? crypto.randomUUID() : ""],
-    );
+      [oxjestVersion, JSON.stringify(options ?? {}), dumpCodeEnabled ? crypto.randomUUID() : ""],
+    ) as NonNullable<SyncTransformer<OxjestOptions>["getCacheKey"]>;
+
+    // Result transformed for the cache key, which `process` called right after `getCacheKey` reuses
+    let lastTransformed: { key: string; sourceText: string; result: ReturnType<typeof transform> } | undefined;
+
+    const transformModule = (
+      sourceText: string,
+      sourcePath: string,
+      transformOptions: TransformOptions<OxjestOptions>,
+    ): ReturnType<typeof transform> => {
+      const key = `${sourcePath}\0${transformOptions.configString}`;
+      if (lastTransformed?.key === key && lastTransformed.sourceText === sourceText) {
+        return lastTransformed.result;
+      }
+
+      const result = transform(sourceText, sourcePath, {
+        resolver: createResolver(transformOptions.config),
+        liveBindings: options?.liveBindings,
+        convertAllImports: options?.convertAllImports,
+        strict: options?.strict,
+        sourceType: options?.sourceType,
+      });
+      lastTransformed = { key, sourceText, result };
+      return result;
+    };
 
     return {
       canInstrument: false,
-      getCacheKey: getCacheKey as SyncTransformer["getCacheKey"],
-      process(sourceText, sourcePath): TransformedSource {
-        const { code, map } = transform(sourceText, sourcePath);
+      getCacheKey(sourceText, sourcePath, transformOptions): string {
+        const cacheKey = getBaseCacheKey(sourceText, sourcePath, transformOptions);
+        if (!mayDependOnImports(sourceText)) {
+          return cacheKey;
+        }
+
+        // Imports to convert depend on the modules read, such as the ones reaching the mocked modules
+        let dependencies: string[];
+        try {
+          ({ dependencies } = transformModule(sourceText, sourcePath, transformOptions));
+        } catch {
+          // Errors are thrown by `process`, whose results are never cached
+          return cacheKey;
+        }
+
+        return dependencies.length > 0 ? `${cacheKey}:${hashDependencies(dependencies)}` : cacheKey;
+      },
+      process(sourceText, sourcePath, transformOptions): TransformedSource {
+        const { code, map, warnings } = transformModule(sourceText, sourcePath, transformOptions);
+        for (const warning of warnings) {
+          console.warn(warning.codeFrame);
+        }
         if (dumpCodeEnabled) {
           console.debug(sourcePath, code);
         }
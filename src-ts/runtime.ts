@@ -1,4 +1,28 @@
 import { jest } from "@jest/globals";
+import * as path from "node:path";
+import { fileURLToPath } from "node:url";
+
+/**
+ * Creates `require` for the module, for the `require` calls that can't be converted into await imports.
+ */
+export { createRequire } from "node:module";
+
+/**
+ * Returns the value `require` returns for the module, from its namespace imported by an await import, for the modules
+ * not known to be ES modules or CommonJS modules statically.
+ * Namespaces of CommonJS modules consist of `default`, which is `module.exports`, and the properties of it.
+ */
+export function interopRequire(namespace: Record<string, unknown>): unknown {
+  const exports = namespace.default;
+  if ((typeof exports !== "object" && typeof exports !== "function") || exports === null) {
+    return namespace;
+  }
+
+  const isCommonJs = Object.keys(namespace).every(
+    (key) => key === "default" || namespace[key] === (exports as Record<string, unknown>)[key],
+  );
+  return isCommonJs ? exports : namespace;
+}
 
 type MockMetadataType = "object" | "array" | "regexp" | "function" | "constant" | "collection" | "null" | "undefined";
 
@@ -206,8 +230,55 @@ function generateMock<T extends object>(metadata: MockMetadata<T>, refs: Record<
   return mock;
 }
 
-export function createMockFactory<T extends Record<string, unknown>>(actual: T): () => jest.Mocked<T> {
-  return () => {
+type MockFactory = () => unknown;
+
+/**
+ * Factories of the mocks registered through the runtime, by the jest objects and the module names.
+ */
+const mockFactories = new WeakMap<typeof jest, Map<string, MockFactory>>();
+
+/**
+ * Registers a mock, remembering its factory to restore the mock after importing the actual module.
+ */
+export function mockModule(jestObject: typeof jest, moduleName: string, factory: MockFactory): typeof jest {
+  let factories = mockFactories.get(jestObject);
+  if (!factories) {
+    factories = new Map();
+    mockFactories.set(jestObject, factories);
+  }
+  factories.set(moduleName, factory);
+
+  return jestObject.unstable_mockModule(moduleName, factory);
+}
+
+/**
+ * Imports the actual module, unmocking it temporarily if it's mocked through the runtime.
+ * The other mocks are kept registered, so that the actual module imports the mocked modules.
+ */
+export async function importActual<T>(jestObject: typeof jest, moduleName: string, load: () => Promise<T>): Promise<T> {
+  const factory = mockFactories.get(jestObject)?.get(moduleName);
+  if (!factory) {
+    return load();
+  }
+
+  jestObject.unstable_unmockModule(moduleName);
+  try {
+    return await load();
+  } finally {
+    jestObject.unstable_mockModule(moduleName, factory);
+  }
+}
+
+/**
+ * Creates a factory generating a mock from the actual module, which is imported once the mocked module is imported.
+ */
+export function createMockFactory<T extends Record<string, unknown>>(
+  jestObject: typeof jest,
+  moduleName: string,
+  load: () => Promise<T>,
+): () => Promise<jest.Mocked<T>> {
+  return async () => {
+    const actual = await importActual(jestObject, moduleName, load);
     const refs: Record<number, unknown> = {};
     const metadata = getMetadata(actual);
     if (!metadata) {
@@ -216,3 +287,57 @@ export function createMockFactory<T extends Record<string, unknown>>(actual: T):
     return generateMock(metadata, refs) as jest.Mocked<T>;
   };
 }
+
+/**
+ * Normalizes the value returned by a mock factory written for CommonJS into a module namespace.
+ * Values other than objects, such as `jest.fn()`, become the default export,
+ * and `{ __esModule: true, default }` objects become the namespaces without the `__esModule` flag.
+ */
+function normalizeModule(module: unknown): Record<string, unknown> {
+  if (typeof module !== "object" || module === null || Array.isArray(module)) {
+    return { default: module };
+  }
+  if (!("__esModule" in module) || !module.__esModule) {
+    return module as Record<string, unknown>;
+  }
+
+  // Keep the getters as they are, which may be used to reference the variables lazily
+  const { __esModule, ...descriptors } = Object.getOwnPropertyDescriptors(module);
+  return Object.defineProperties({}, descriptors);
+}
+
+/**
+ * Wraps a mock factory written for CommonJS, to return a module namespace.
+ * Async factories, which import the actual modules lazily, resolve to a module namespace.
+ */
+export function normalizeFactory(
+  factory: () => unknown,
+): () => Record<string, unknown> | Promise<Record<string, unknown>> {
+  return () => {
+    const module = factory();
+    return module instanceof Promise ? module.then(normalizeModule) : normalizeModule(module);
+  };
+}
+
+/**
+ * Registers a virtual mock, which is a module that does not exist on disk.
+ * The mock is registered for CommonJS too, so that the resolver knows the module is virtual.
+ */
+export function mockVirtualModule(jestObject: typeof jest, moduleName: string, factory: () => unknown): typeof jest {
+  jestObject.mock(moduleName, factory, { virtual: true });
+  return jestObject.unstable_mockModule(moduleName, normalizeFactory(factory), { virtual: true });
+}
+
+/**
+ * Returns the file path of the module, as `__filename` and `import.meta.filename`.
+ */
+export function filename(url: string): string {
+  return fileURLToPath(url);
+}
+
+/**
+ * Returns the directory path of the module, as `__dirname` and `import.meta.dirname`.
+ */
+export function dirname(url: string): string {
+  return path.dirname(fileURLToPath(url));
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use oxc::diagnostics::{
+    GraphicalReportHandler, GraphicalTheme, NamedSource, OxcDiagnostic, Severity,
+};
+
+/// A diagnostic reported while loading or transforming a module, located in the source.
+#[derive(Debug)]
+#[napi(object)]
+pub struct Diagnostic {
+    /// Either `"error"`, `"warning"` or `"advice"`.
+    pub severity: String,
+    pub message: String,
+    pub help: Option<String>,
+    /// Path of the module.
+    pub file: String,
+    /// Line number of the primary label, starting from 1.
+    pub line: u32,
+    /// Column number of the primary label in characters, starting from 1.
+    pub column: u32,
+    /// The diagnostic rendered with the source code around its labels.
+    pub code_frame: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(diagnostic: OxcDiagnostic, source_text: &str, source_path: &Path) -> Self {
+        let file = source_path.to_string_lossy().into_owned();
+
+        // Offset of the primary label, or the first one
+        let labels = diagnostic.labels.iter().flatten();
+        let offset = labels
+            .clone()
+            .find(|label| label.primary())
+            .or_else(|| labels.clone().next())
+            .map_or(0, |label| label.offset())
+            .min(source_text.len());
+        let before = &source_text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+
+        let severity = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Advice => "advice",
+        };
+        let message = diagnostic.message.to_string();
+        let help = diagnostic.help.as_ref().map(ToString::to_string);
+
+        let mut code_frame = String::new();
+        let report = diagnostic.with_source_code(NamedSource::new(&file, source_text.to_string()));
+        // Writing into a string never fails
+        let _ = GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .with_width(usize::MAX)
+            .render_report(&mut code_frame, report.as_ref());
+
+        Self {
+            severity: severity.to_string(),
+            message,
+            help,
+            file,
+            line: line as u32,
+            column: column as u32,
+            code_frame: code_frame.trim_matches('\n').to_string(),
+        }
+    }
+}
+
+/// Diagnostics failing the transform, which are thrown as an error with `diagnostics` property.
+#[derive(Debug)]
+pub(crate) struct TransformError {
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+impl TransformError {
+    pub(crate) fn new(
+        diagnostics: impl IntoIterator<Item = OxcDiagnostic>,
+        source_text: &str,
+        source_path: &Path,
+    ) -> Self {
+        Self {
+            diagnostics: diagnostics
+                .into_iter()
+                .map(|diagnostic| Diagnostic::new(diagnostic, source_text, source_path))
+                .collect(),
+        }
+    }
+
+    /// Formats the diagnostics with their code frames, for the message of the error.
+    pub(crate) fn message(&self) -> String {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code_frame.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Converts into a JS error carrying the diagnostics in `diagnostics` property.
+    pub(crate) fn into_napi_error(self, env: &Env) -> Error {
+        let message = self.message();
+
+        env.create_error(Error::from_reason(message.clone()))
+            .and_then(|mut error| {
+                error.set_named_property("diagnostics", self.diagnostics)?;
+                Ok(Error::from(error.to_unknown()))
+            })
+            .unwrap_or_else(|_| Error::from_reason(message))
+    }
+}
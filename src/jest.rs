@@ -1,4 +1,5 @@
 use oxc::ast::ast::{CallExpression, Expression};
+use oxc::semantic::Scoping;
 
 /// Checks that the expression is `jest.meta`.
 fn is_import_meta(expr: &Expression) -> bool {
@@ -13,12 +14,20 @@ fn is_import_meta(expr: &Expression) -> bool {
         && member.property.name == META
 }
 
-/// Checks that the expression is `jest` or `import.meta.jest`.
-fn is_jest_object(expr: &Expression) -> bool {
+/// Checks that the expression is the global `jest` or `import.meta.jest`, rather than a local binding named `jest`.
+/// Imported jest objects are turned into `import.meta.jest` by [`InjectGlobals`](crate::pass::inject_globals::InjectGlobals)
+/// before the other transforms see them.
+pub fn is_jest_object(expr: &Expression, scoping: &Scoping) -> bool {
     const JEST_OBJECT_NAME: &str = "jest";
 
     match expr {
-        Expression::Identifier(ident) => ident.name == JEST_OBJECT_NAME,
+        // References without IDs are the ones created by the transforms
+        Expression::Identifier(ident) => {
+            ident.name == JEST_OBJECT_NAME
+                && ident.reference_id.get().is_none_or(|reference_id| {
+                    scoping.get_reference(reference_id).symbol_id().is_none()
+                })
+        }
         Expression::StaticMemberExpression(member) if is_import_meta(&member.object) => {
             member.property.name == JEST_OBJECT_NAME
         }
@@ -26,35 +35,154 @@ fn is_jest_object(expr: &Expression) -> bool {
     }
 }
 
-/// Checks that the call expression is `jest.requireActual(...)`.
-pub fn is_jest_require_actual_call(expr: &CallExpression) -> bool {
-    const REQUIRE_ACTUAL: &str = "requireActual";
-
+/// Checks that the expression is `jest.<method>`.
+fn is_jest_method(expr: &Expression, method: &str, scoping: &Scoping) -> bool {
     matches!(
-        &expr.callee,
-        Expression::StaticMemberExpression(callee)
-            if is_jest_object(&callee.object) && callee.property.name == REQUIRE_ACTUAL
+        expr,
+        Expression::StaticMemberExpression(member)
+            if is_jest_object(&member.object, scoping) && member.property.name == method
     )
 }
 
-/// Checks that the call expression is `jest.mock(...)`.
-pub fn is_jest_mock_call(expr: &CallExpression) -> bool {
-    const MOCK: &str = "mock";
+/// Checks that the callee is a test or a hook, or their variants such as `test.each(...)` and `it.only`,
+/// whose callbacks may be async.
+pub fn is_test_callee(callee: &Expression) -> bool {
+    const NAMES: [&str; 9] = [
+        "test",
+        "it",
+        "fit",
+        "xit",
+        "xtest",
+        "beforeAll",
+        "beforeEach",
+        "afterAll",
+        "afterEach",
+    ];
 
-    matches!(
-        &expr.callee,
-        Expression::StaticMemberExpression(callee)
-            if is_jest_object(&callee.object) && callee.property.name == MOCK
-    )
+    match callee {
+        Expression::Identifier(ident) => NAMES.contains(&ident.name.as_str()),
+        Expression::StaticMemberExpression(member) => is_test_callee(&member.object),
+        Expression::CallExpression(call) => is_test_callee(&call.callee),
+        Expression::TaggedTemplateExpression(tagged) => is_test_callee(&tagged.tag),
+        _ => false,
+    }
+}
+
+/// Checks that the call expression is `jest.<method>(...)`.
+fn is_jest_method_call(expr: &CallExpression, method: &str, scoping: &Scoping) -> bool {
+    is_jest_method(&expr.callee, method, scoping)
+}
+
+/// Checks that the call expression is `jest.fn(...)`.
+pub fn is_jest_fn_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "fn", scoping)
+}
+
+/// Checks that the call expression is `jest.requireActual(...)`.
+pub fn is_jest_require_actual_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "requireActual", scoping)
+}
+
+/// Checks that the call expression is `jest.requireMock(...)`.
+pub fn is_jest_require_mock_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "requireMock", scoping)
+}
+
+/// Checks that the call expression is `jest.mock(...)`.
+pub fn is_jest_mock_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "mock", scoping)
 }
 
 /// Checks that the call expression is `jest.doMock(...)`.
-pub fn is_jest_do_mock_call(expr: &CallExpression) -> bool {
-    const DO_MOCK: &str = "doMock";
+pub fn is_jest_do_mock_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "doMock", scoping)
+}
 
-    matches!(
-        &expr.callee,
-        Expression::StaticMemberExpression(callee)
-            if is_jest_object(&callee.object) && callee.property.name == DO_MOCK
-    )
+/// Checks that the call expression is `jest.unstable_mockModule(...)`.
+pub fn is_jest_unstable_mock_module_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "unstable_mockModule", scoping)
+}
+
+/// Checks that the call expression is `jest.unmock(...)`.
+pub fn is_jest_unmock_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "unmock", scoping)
+}
+
+/// Checks that the call expression is `jest.dontMock(...)`.
+pub fn is_jest_dont_mock_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_method_call(expr, "dontMock", scoping)
+}
+
+/// Checks that the call expression is `jest.isolateModules(...)`.
+pub fn is_jest_isolate_modules_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    is_jest_isolate_modules_callee(&expr.callee, scoping)
+}
+
+/// Checks that the callee is `jest.isolateModules`.
+pub fn is_jest_isolate_modules_callee(callee: &Expression, scoping: &Scoping) -> bool {
+    is_jest_method(callee, "isolateModules", scoping)
+}
+
+/// Checks that the callee is `jest.isolateModulesAsync`, whose callback may be async.
+pub fn is_jest_isolate_modules_async_callee(callee: &Expression, scoping: &Scoping) -> bool {
+    is_jest_method(callee, "isolateModulesAsync", scoping)
+}
+
+/// Checks that the call expression is a jest method taking a module specifier, such as `jest.mock(...)`.
+pub fn is_jest_module_call(expr: &CallExpression, scoping: &Scoping) -> bool {
+    const METHODS: [&str; 7] = [
+        "mock",
+        "doMock",
+        "unstable_mockModule",
+        "unmock",
+        "dontMock",
+        "requireActual",
+        "requireMock",
+    ];
+
+    METHODS
+        .iter()
+        .any(|method| is_jest_method_call(expr, method, scoping))
+}
+
+/// Checks that the callee is a jest method taking a mock factory, such as `jest.mock`.
+pub fn is_jest_mock_factory_callee(callee: &Expression, scoping: &Scoping) -> bool {
+    const METHODS: [&str; 3] = ["mock", "doMock", "unstable_mockModule"];
+
+    METHODS
+        .iter()
+        .any(|method| is_jest_method(callee, method, scoping))
+}
+
+/// Checks that the expression is a chain of calls registering or unregistering mocks, such as
+/// `jest.mock(...).unmock(...)`, which is possible as the calls return the jest object.
+pub fn is_jest_mock_chain(expr: &Expression, scoping: &Scoping) -> bool {
+    const METHODS: [&str; 5] = [
+        "mock",
+        "doMock",
+        "unstable_mockModule",
+        "unmock",
+        "dontMock",
+    ];
+
+    let mut expr = expr;
+    let mut length = 0;
+    loop {
+        let Expression::CallExpression(call) = expr else {
+            return false;
+        };
+        let Expression::StaticMemberExpression(callee) = &call.callee else {
+            return false;
+        };
+        if !METHODS.contains(&callee.property.name.as_str()) {
+            return false;
+        }
+
+        length += 1;
+        if is_jest_object(&callee.object, scoping) {
+            return length > 1;
+        }
+
+        expr = &callee.object;
+    }
 }
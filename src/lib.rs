@@ -1,17 +1,46 @@
+mod diagnostic;
 mod jest;
 mod loader;
+mod module_graph;
 mod pass;
 mod transform;
 
 #[cfg(test)]
 mod testing;
 
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
+use oxc::span::SourceType;
+
+pub use crate::diagnostic::Diagnostic;
+use crate::module_graph::Resolver;
 
 #[napi(object)]
 pub struct TransformOptions<'scope> {
-    pub resolver: Function<'scope, (String, String), String>,
+    /// Resolves the specifier imported from the path into a path, or throws if it's not resolvable.
+    /// Only the imports that may reach the mocked modules are converted into dynamic imports if given.
+    pub resolver: Option<Function<'scope, (String, String), String>>,
+    /// Whether to keep the live bindings of the imports converted into dynamic imports. Defaults to `true`.
+    pub live_bindings: Option<bool>,
+    /// Whether to convert all imports in modules with hoisted mocks, even if `resolver` is given.
+    /// Defaults to `false`.
+    pub convert_all_imports: Option<bool>,
+    /// Whether to throw on the warnings as well as the errors, such as mocks that can't be converted.
+    /// Defaults to `false`.
+    pub strict: Option<bool>,
+    /// Source type of the modules with extensions unknown to Oxc, such as `.es6`, as an extension such as `"js"`,
+    /// `"jsx"`, `"ts"` or `"tsx"`. The source types of the other modules are inferred from their extensions.
+    pub source_type: Option<String>,
+}
+
+impl Resolver for Function<'_, (String, String), String> {
+    fn resolve(&self, specifier: &str, from: &Path) -> Option<String> {
+        self.call((specifier.to_string(), from.to_string_lossy().into_owned()))
+            .ok()
+    }
 }
 
 #[derive(Debug)]
@@ -19,9 +48,61 @@ pub struct TransformOptions<'scope> {
 pub struct TransformedSource {
     pub code: String,
     pub map: String,
+    /// Warnings about the constructs left untransformed, which may not work as expected in ESM.
+    pub warnings: Vec<Diagnostic>,
+    /// Paths of the modules read to find the imports reaching the mocked modules, which the code depends on as well
+    /// as the source.
+    pub dependencies: Vec<String>,
 }
 
+/// Transforms the module, or throws an error with `diagnostics` property, an array of the [`Diagnostic`]s.
 #[napi]
-pub fn transform(source_text: String, source_path: String) -> Result<TransformedSource> {
-    transform::_transform(source_text, source_path)
+pub fn transform(
+    env: Env,
+    source_text: String,
+    source_path: String,
+    options: Option<TransformOptions>,
+) -> Result<TransformedSource> {
+    let mut transformer_options = pass::TransformerOptions::default();
+    if let Some(options) = &options {
+        if let Some(live_bindings) = options.live_bindings {
+            transformer_options.live_bindings = live_bindings;
+        }
+        if let Some(convert_all_imports) = options.convert_all_imports {
+            transformer_options.convert_all_imports = convert_all_imports;
+        }
+        if let Some(strict) = options.strict {
+            transformer_options.strict = strict;
+        }
+        if let Some(source_type) = &options.source_type {
+            transformer_options.source_type =
+                Some(SourceType::from_extension(source_type).map_err(|_| {
+                    Error::new(
+                        Status::InvalidArg,
+                        format!("Unknown source type `{source_type}`, which must be an extension such as `js` or `ts`."),
+                    )
+                })?);
+        }
+        transformer_options.resolver = options
+            .resolver
+            .as_ref()
+            .map(|resolver| resolver as &dyn Resolver);
+    }
+
+    // Panics must not unwind across the boundary, which would take down the Jest worker
+    let path = source_path.clone();
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        transform::_transform(source_text, source_path, &transformer_options)
+    }))
+    .map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        Error::from_reason(format!(
+            "oxjest panicked while transforming {path}: {message}"
+        ))
+    })?
+    .map_err(|error| error.into_napi_error(&env))
 }
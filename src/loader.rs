@@ -11,14 +11,28 @@ use oxc::transformer::{DecoratorOptions, TransformOptions, Transformer, Transfor
 pub struct Loader;
 
 impl Loader {
+    /// Parses and transpiles the module, as the source type inferred from the extension of the path, or the fallback
+    /// source type given for unknown extensions.
     pub fn load_str<'a>(
         &self,
         allocator: &'a Allocator,
         source_text: &'a str,
         source_path: impl AsRef<Path>,
+        fallback_source_type: Option<SourceType>,
     ) -> Result<(Program<'a>, Scoping), Vec<OxcDiagnostic>> {
         let source_path = source_path.as_ref();
-        let source_type = SourceType::from_path(source_path).unwrap();
+        let Some(source_type) = SourceType::from_path(source_path)
+            .ok()
+            .or(fallback_source_type)
+        else {
+            return Err(vec![
+                OxcDiagnostic::error(format!(
+                    "Cannot infer the source type of `{}` from its extension.",
+                    source_path.display()
+                ))
+                .with_help("Specify `sourceType` option, such as `\"js\"` or `\"ts\"`, for the modules with unknown extensions."),
+            ]);
+        };
 
         let ParserReturn {
             mut program,
@@ -0,0 +1,427 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use oxc::allocator::Allocator;
+use oxc::ast::ast::{Argument, CallExpression, Expression, ImportExpression};
+use oxc::ast_visit::{Visit, walk};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::parser::{Parser, ParserReturn};
+use oxc::span::{SourceType, Span};
+use oxc::syntax::module_record::ExportExportName;
+
+/// Resolves module specifiers into paths, as Jest does.
+pub trait Resolver {
+    /// Resolves the specifier imported from the module at the path, or returns `None` if it's not resolvable.
+    /// Built-in modules are resolved into their names rather than paths.
+    fn resolve(&self, specifier: &str, from: &Path) -> Option<String>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(&str, &Path) -> Option<String>,
+{
+    fn resolve(&self, specifier: &str, from: &Path) -> Option<String> {
+        self(specifier, from)
+    }
+}
+
+fn is_in_node_modules(path: &Path) -> bool {
+    path.components()
+        .any(|component| component == Component::Normal("node_modules".as_ref()))
+}
+
+/// Collects the modules loaded by `import("...")` and `require("...")`, which may load the mocked modules lazily.
+#[derive(Default)]
+struct LoadedModules {
+    specifiers: Vec<String>,
+    /// Whether any module is loaded by a specifier unknown statically.
+    has_unknown: bool,
+}
+
+impl LoadedModules {
+    fn record(&mut self, source: Option<&Expression>) {
+        match source {
+            Some(Expression::StringLiteral(lit)) => self.specifiers.push(lit.value.to_string()),
+            _ => self.has_unknown = true,
+        }
+    }
+}
+
+impl<'a> Visit<'a> for LoadedModules {
+    fn visit_import_expression(&mut self, expr: &ImportExpression<'a>) {
+        self.record(Some(&expr.source));
+        walk::walk_import_expression(self, expr);
+    }
+
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if call.callee.is_specific_id("require") {
+            self.record(call.arguments.first().and_then(Argument::as_expression));
+        }
+        walk::walk_call_expression(self, call);
+    }
+}
+
+/// Module read from the disk.
+#[derive(Clone)]
+struct ModuleInfo {
+    /// Specifiers of the modules imported by the module, including `import("...")` and `require("...")`, except
+    /// type-only imports.
+    imports: Vec<String>,
+    /// Whether the module loads modules by the specifiers unknown statically, which may be any modules.
+    imports_unknown: bool,
+    /// Whether the module has `import` or `export` statements, rather than being a CommonJS module.
+    is_es_module: bool,
+    /// Names exported by the module, except the ones of `export * from` and type-only exports.
+    exports: Vec<String>,
+    /// Specifiers of the modules re-exported by `export * from`, except type-only re-exports.
+    star_exports: Vec<String>,
+}
+
+/// Graph of the imports between the modules, which are read from the disk lazily.
+pub struct ModuleGraph<'r> {
+    resolver: &'r dyn Resolver,
+    source_path: PathBuf,
+    /// Modules resolved from the source module, by their specifiers.
+    resolved: RefCell<HashMap<String, Option<String>>>,
+    /// Modules read from the disk, or `None` if the module couldn't be parsed.
+    modules: RefCell<HashMap<PathBuf, Option<ModuleInfo>>>,
+}
+
+impl<'r> ModuleGraph<'r> {
+    pub fn new(resolver: &'r dyn Resolver, source_path: impl Into<PathBuf>) -> Self {
+        Self {
+            resolver,
+            source_path: source_path.into(),
+            resolved: RefCell::new(HashMap::new()),
+            modules: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Resolves the specifier imported from the module at the path. Built-in modules are resolved into their names
+    /// without the `node:` prefix, as `fs` and `node:fs` are the same module.
+    fn resolve_from(&self, specifier: &str, from: &Path) -> Option<String> {
+        let resolved = self.resolver.resolve(specifier, from)?;
+        if Path::new(&resolved).is_absolute() {
+            return Some(resolved);
+        }
+
+        match resolved.strip_prefix("node:") {
+            Some(name) => Some(name.to_string()),
+            None => Some(resolved),
+        }
+    }
+
+    /// Resolves the specifier imported from the source module.
+    pub fn resolve(&self, specifier: &str) -> Option<String> {
+        self.resolved
+            .borrow_mut()
+            .entry(specifier.to_string())
+            .or_insert_with(|| self.resolve_from(specifier, &self.source_path))
+            .clone()
+    }
+
+    /// Checks that the specifier at the span is resolvable, or returns a warning.
+    pub fn check_resolvable(&self, specifier: &str, span: Span) -> Option<OxcDiagnostic> {
+        if self.resolve(specifier).is_some() {
+            return None;
+        }
+
+        Some(
+            OxcDiagnostic::warn(format!("Cannot resolve module `{specifier}`."))
+                .with_label(span.label("Not resolvable"))
+                .with_help("Check the path, `moduleNameMapper`, or mark virtual modules with `{ virtual: true }`."),
+        )
+    }
+
+    /// Reads and parses the module.
+    fn read_module(path: &Path) -> Option<ModuleInfo> {
+        // Modules other than JavaScript, such as JSON, import nothing
+        let Ok(source_type) = SourceType::from_path(path) else {
+            return Some(ModuleInfo {
+                imports: Vec::new(),
+                imports_unknown: false,
+                is_es_module: false,
+                exports: Vec::new(),
+                star_exports: Vec::new(),
+            });
+        };
+
+        let source_text = std::fs::read_to_string(path).ok()?;
+        let allocator = Allocator::new();
+        let ParserReturn {
+            program,
+            module_record,
+            errors,
+            panicked,
+            ..
+        } = Parser::new(&allocator, &source_text, source_type).parse();
+        if panicked || !errors.is_empty() {
+            return None;
+        }
+
+        let mut loaded_modules = LoadedModules::default();
+        loaded_modules.visit_program(&program);
+
+        let imports = module_record
+            .requested_modules
+            .iter()
+            .filter(|(_, requests)| requests.iter().any(|request| !request.is_type))
+            .map(|(specifier, _)| specifier.to_string())
+            .chain(loaded_modules.specifiers)
+            .collect();
+        let exports = module_record
+            .local_export_entries
+            .iter()
+            .chain(&module_record.indirect_export_entries)
+            .filter(|entry| !entry.is_type)
+            .filter_map(|entry| match &entry.export_name {
+                ExportExportName::Name(name) => Some(name.name.to_string()),
+                ExportExportName::Default(_) => Some("default".to_string()),
+                ExportExportName::Null => None,
+            })
+            .collect();
+        let star_exports = module_record
+            .star_export_entries
+            .iter()
+            .filter(|entry| !entry.is_type)
+            .filter_map(|entry| Some(entry.module_request.as_ref()?.name.to_string()))
+            .collect();
+
+        Some(ModuleInfo {
+            imports,
+            imports_unknown: loaded_modules.has_unknown,
+            is_es_module: module_record.has_module_syntax,
+            exports,
+            star_exports,
+        })
+    }
+
+    /// Returns the module at the path, which is read from the disk once.
+    fn module(&self, path: &Path) -> Option<ModuleInfo> {
+        self.modules
+            .borrow_mut()
+            .entry(path.to_path_buf())
+            .or_insert_with(|| Self::read_module(path))
+            .clone()
+    }
+
+    /// Checks that the module imported by the specifier is an ES module rather than a CommonJS module, or returns
+    /// `None` if it can't be resolved or read. Built-in modules are CommonJS modules.
+    pub fn is_es_module(&self, specifier: &str) -> Option<bool> {
+        let path = PathBuf::from(self.resolve(specifier)?);
+        if !path.is_absolute() {
+            return Some(false);
+        }
+
+        self.module(&path).map(|module| module.is_es_module)
+    }
+
+    /// Returns the paths of the modules read from the disk in order, on which the results of the graph depend.
+    pub fn dependencies(&self) -> Vec<PathBuf> {
+        let mut paths = self.modules.borrow().keys().cloned().collect::<Vec<_>>();
+        paths.sort_unstable();
+        paths
+    }
+
+    /// Returns the names re-exported by `export * from` the specifier, or `None` if they're unknown, such as the ones
+    /// of CommonJS modules which are known only after evaluating the modules.
+    pub fn star_exports(&self, specifier: &str) -> Option<Vec<String>> {
+        let mut names = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_star_exports(
+            PathBuf::from(self.resolve(specifier)?),
+            &mut names,
+            &mut visited,
+        )?;
+
+        Some(names)
+    }
+
+    fn collect_star_exports(
+        &self,
+        path: PathBuf,
+        names: &mut Vec<String>,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Option<()> {
+        if !path.is_absolute() {
+            return None;
+        }
+
+        if !visited.insert(path.clone()) {
+            return Some(());
+        }
+
+        let module = self.module(&path).filter(|module| module.is_es_module)?;
+
+        // `export * from` never re-exports the default export, and the names exported by the module itself take
+        // precedence over the ones re-exported from the other modules
+        for name in module.exports {
+            if name != "default" && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        for specifier in module.star_exports {
+            let resolved = self.resolve_from(&specifier, &path)?;
+            self.collect_star_exports(PathBuf::from(resolved), names, visited)?;
+        }
+
+        Some(())
+    }
+
+    /// Checks that the module imported by the specifier may reach any of the targets through imports, including
+    /// `import("...")` and `require("...")`. Modules that can't be resolved or read, or that load modules by the
+    /// specifiers unknown statically, are assumed to reach the targets.
+    pub fn reaches(&self, specifier: &str, targets: &HashSet<String>) -> bool {
+        let Some(resolved) = self.resolve(specifier) else {
+            return true;
+        };
+
+        // Packages never import the modules outside of the packages, except for other packages and built-in modules
+        let has_package_target = targets.iter().any(|target| {
+            let path = Path::new(target);
+            !path.is_absolute() || is_in_node_modules(path)
+        });
+
+        let mut visited = HashSet::new();
+        let mut queue = vec![resolved];
+        while let Some(id) = queue.pop() {
+            if targets.contains(&id) {
+                return true;
+            }
+
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+
+            // Built-in modules import nothing to be mocked
+            let path = PathBuf::from(id);
+            if !path.is_absolute() || (!has_package_target && is_in_node_modules(&path)) {
+                continue;
+            }
+
+            let Some(module) = self.module(&path).filter(|module| !module.imports_unknown) else {
+                return true;
+            };
+
+            for specifier in module.imports {
+                let Some(resolved) = self.resolve_from(&specifier, &path) else {
+                    return true;
+                };
+
+                queue.push(resolved);
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    use super::ModuleGraph;
+    use crate::testing::{fixture_path, resolve};
+
+    /// Resolves `fs` and `node:` specifiers as built-in modules, and the others as [`resolve`] does.
+    fn resolve_builtins(specifier: &str, from: &Path) -> Option<String> {
+        if specifier == "fs" || specifier.starts_with("node:") {
+            return Some(specifier.to_string());
+        }
+
+        resolve(specifier, from)
+    }
+
+    /// Writes a module importing `node:fs`, and a package importing `fs`, into the temporary directory of the name.
+    fn write_builtin_importers(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oxjest-{name}"));
+        let package_dir = dir.join("node_modules").join("reader");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(
+            package_dir.join("index.js"),
+            "export { readFileSync as read } from \"fs\";\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("reader.js"),
+            "export { readFileSync as read } from \"node:fs\";\n",
+        )
+        .unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_reaches() {
+        let graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let targets = HashSet::from([graph.resolve("./counter.ts").unwrap()]);
+
+        assert!(graph.reaches("./counter.ts", &targets));
+        assert!(graph.reaches("./counterReporter.ts", &targets));
+        assert!(!graph.reaches("./greeter.ts", &targets));
+        assert!(graph.reaches("./missing.ts", &targets));
+
+        assert_eq!(
+            graph.dependencies(),
+            vec![
+                fixture_path("counterReporter.ts"),
+                fixture_path("greeter.ts"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_reaches_lazily() {
+        let graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let targets = HashSet::from([graph.resolve("./defaultGreeter.ts").unwrap()]);
+
+        assert!(graph.reaches("./lazyGreeter.ts", &targets));
+        assert!(graph.reaches("./lazyLoader.ts", &targets));
+        assert!(!graph.reaches("./commonjsGreeter.cjs", &targets));
+    }
+
+    #[test]
+    fn test_reaches_builtins() {
+        let dir = write_builtin_importers("reaches-builtins");
+        let graph = ModuleGraph::new(&resolve_builtins, dir.join("source.test.js"));
+
+        // `fs` and `node:fs` are the same module
+        let targets = HashSet::from([graph.resolve("fs").unwrap()]);
+        assert!(graph.reaches("./reader.js", &targets));
+
+        let targets = HashSet::from([graph.resolve("node:fs").unwrap()]);
+        assert!(graph.reaches("./reader.js", &targets));
+    }
+
+    #[test]
+    fn test_reaches_builtins_from_packages() {
+        let dir = write_builtin_importers("reaches-builtins-from-packages");
+        let graph = ModuleGraph::new(&resolve_builtins, dir.join("source.test.js"));
+
+        // Packages may import the built-in modules
+        let targets = HashSet::from([graph.resolve("node:fs").unwrap()]);
+        assert!(graph.reaches("./node_modules/reader/index.js", &targets));
+
+        let targets = HashSet::from([graph.resolve("./reader.js").unwrap()]);
+        assert!(!graph.reaches("./node_modules/reader/index.js", &targets));
+    }
+
+    #[test]
+    fn test_star_exports() {
+        let graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+
+        assert_eq!(
+            graph.star_exports("./starExports.ts"),
+            Some(vec![
+                "counter".to_string(),
+                "greet".to_string(),
+                "Greeter".to_string(),
+            ]),
+        );
+        assert_eq!(graph.star_exports("./commonjsGreeter.cjs"), None);
+        assert_eq!(graph.star_exports("./missing.ts"), None);
+    }
+}
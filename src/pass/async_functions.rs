@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use oxc::ast::ast::Expression;
+use oxc::semantic::{ScopeId, Scoping};
+use oxc_traverse::{Ancestor, TraverseCtx};
+
+use crate::jest::{
+    is_jest_isolate_modules_async_callee, is_jest_isolate_modules_callee, is_test_callee,
+};
+
+/// Where the current node is evaluated, to check whether `await` can be used there.
+pub(crate) enum AwaitScope<'a, 't> {
+    /// The top level of the module, where `await` is always allowed.
+    Program,
+    /// The body of a function, which needs to be made async to use `await`.
+    Function {
+        scope_id: ScopeId,
+        /// Callee of the call taking the function as an argument, such as `test` of `test("...", () => {})`.
+        callee: Option<&'t Expression<'a>>,
+        /// Whether the function is already async, or may be made async as its caller awaits it.
+        is_awaitable: bool,
+    },
+}
+
+impl<'a, 't> AwaitScope<'a, 't> {
+    /// Returns the scope of the current node, or `None` if `await` can't be used there even if made async,
+    /// such as in parameters, generators and classes.
+    pub(crate) fn from_ctx<State>(ctx: &'t TraverseCtx<'a, State>) -> Option<Self> {
+        Self::from_ancestors(&mut ctx.ancestors(), ctx.scoping())
+    }
+
+    fn from_ancestors(
+        ancestors: &mut impl Iterator<Item = Ancestor<'a, 't>>,
+        scoping: &Scoping,
+    ) -> Option<Self> {
+        let scope = ancestors.find_map(|ancestor| match ancestor {
+            Ancestor::ArrowFunctionExpressionBody(func) => Some(
+                func.scope_id()
+                    .get()
+                    .map(|scope_id| (scope_id, *func.r#async(), !func.params().items.is_empty())),
+            ),
+            Ancestor::FunctionBody(func) if !func.generator() => Some(
+                func.scope_id()
+                    .get()
+                    .map(|scope_id| (scope_id, *func.r#async(), !func.params().items.is_empty())),
+            ),
+            ancestor
+                if ancestor.is_function()
+                    || ancestor.is_arrow_function_expression()
+                    || ancestor.is_class() =>
+            {
+                Some(None)
+            }
+            _ => None,
+        });
+
+        let Some(scope) = scope else {
+            return Some(Self::Program);
+        };
+        let (scope_id, is_async, has_params) = scope?;
+
+        let callee = match ancestors.next() {
+            Some(Ancestor::CallExpressionArguments(call)) => Some(call.callee()),
+            _ => None,
+        };
+
+        // Only the callbacks awaited by Jest are made async, as the others may be called synchronously,
+        // such as `[].map(() => {})`. Test callbacks taking `done` can't return promises, and `jest.isolateModules`
+        // is awaited once converted, if the call can be awaited.
+        let is_awaitable = is_async
+            || callee.is_some_and(|callee| {
+                (is_test_callee(callee) && !has_params)
+                    || is_jest_isolate_modules_async_callee(callee, scoping)
+                    || (is_jest_isolate_modules_callee(callee, scoping)
+                        && Self::from_ancestors(ancestors, scoping)
+                            .is_some_and(|scope| scope.is_awaitable()))
+            });
+
+        Some(Self::Function {
+            scope_id,
+            callee,
+            is_awaitable,
+        })
+    }
+
+    /// Checks that `await` can be used once the function is made async.
+    pub(crate) fn is_awaitable(&self) -> bool {
+        match self {
+            Self::Program => true,
+            Self::Function { is_awaitable, .. } => *is_awaitable,
+        }
+    }
+}
+
+/// Functions to be made async, as `await` is inserted into them.
+pub(crate) struct AsyncFunctions {
+    scopes: HashSet<ScopeId>,
+}
+
+impl AsyncFunctions {
+    pub(crate) fn new() -> Self {
+        Self {
+            scopes: HashSet::new(),
+        }
+    }
+
+    /// Makes the function async when exiting it.
+    pub(crate) fn insert(&mut self, scope_id: ScopeId) {
+        self.scopes.insert(scope_id);
+    }
+
+    /// Makes the function async if it's registered, which must be called on exiting expressions.
+    pub(crate) fn exit_expression(&self, node: &mut Expression) {
+        match node {
+            Expression::ArrowFunctionExpression(func)
+                if func
+                    .scope_id
+                    .get()
+                    .is_some_and(|scope_id| self.scopes.contains(&scope_id)) =>
+            {
+                func.r#async = true;
+            }
+            Expression::FunctionExpression(func)
+                if func
+                    .scope_id
+                    .get()
+                    .is_some_and(|scope_id| self.scopes.contains(&scope_id)) =>
+            {
+                func.r#async = true;
+            }
+            _ => {}
+        }
+    }
+}
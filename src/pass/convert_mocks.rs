@@ -1,169 +1,555 @@
+use std::collections::HashSet;
 use std::iter::once;
-use std::sync::atomic::{AtomicUsize, Ordering};
 
 use oxc::allocator::{Box, CloneIn, TakeIn, Vec as ArenaVec};
 use oxc::ast::AstBuilder;
 use oxc::ast::ast::{
-    Argument, BindingPatternKind, Expression, ImportDeclaration, ImportDeclarationSpecifier,
-    Program, Span, Statement, VariableDeclaration, VariableDeclarationKind,
+    Argument, CallExpression, Expression, ImportPhase, ObjectPropertyKind, Program, Span,
+    Statement, StringLiteral,
 };
-use oxc::span::GetSpan;
-use oxc_traverse::{Traverse, TraverseCtx};
+use oxc::ast_visit::VisitMut;
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::semantic::{ReferenceFlags, Scoping, SymbolId};
+use oxc::span::{Atom, GetSpanMut};
+use oxc_traverse::{Ancestor, Traverse, TraverseCtx};
+
+mod dynamic_imports;
+mod factory;
+mod live_bindings;
 
 use crate::jest::is_jest_do_mock_call;
+use crate::jest::is_jest_dont_mock_call;
 use crate::jest::is_jest_mock_call;
+use crate::jest::is_jest_mock_chain;
+use crate::jest::is_jest_object;
+use crate::jest::is_jest_require_mock_call;
+use crate::jest::is_jest_unmock_call;
+use crate::jest::is_jest_unstable_mock_module_call;
+use crate::module_graph::ModuleGraph;
+use crate::pass::convert_mocks::dynamic_imports::{DynamicImports, collect_export_names};
+use crate::pass::convert_mocks::factory::{FactoryReferences, take_variables};
+use crate::pass::convert_mocks::live_bindings::{LiveBindings, collect_local_exports};
+use crate::pass::convert_require::is_require_call;
+use crate::pass::runtime::{make_lazy_import, make_runtime_call};
 
-fn make_create_mock_factory<'a>(ast: AstBuilder<'a>, id: &'a str) -> Expression<'a> {
-    ast.expression_call(
+/// Makes `__oxjest__.createMockFactory(jest, id, () => import(id))`, which imports the actual module lazily,
+/// once the mocked module is imported after all the mocks are registered.
+fn make_create_mock_factory<'a>(
+    ast: AstBuilder<'a>,
+    jest: Expression<'a>,
+    id: &StringLiteral<'a>,
+) -> Expression<'a> {
+    make_runtime_call(
+        ast,
         Span::default(),
-        ast.member_expression_static(
-            Span::default(),
-            ast.expression_identifier(Span::default(), "__oxjest__"),
-            ast.identifier_name(Span::default(), "createMockFactory"),
-            false,
-        )
-        .into(),
-        Option::<Box<'_, _>>::None,
-        ast.vec1(
-            ast.expression_await(
-                Span::default(),
-                ast.expression_import(
-                    Span::default(),
-                    ast.expression_string_literal(Span::default(), id, None),
-                    None,
-                    None,
-                ),
-            )
-            .into(),
-        ),
-        false,
+        "createMockFactory",
+        [
+            jest.into(),
+            ast.expression_string_literal(Span::default(), id.value, None)
+                .into(),
+            make_lazy_import(ast, id.value.as_str()).into(),
+        ],
     )
 }
 
-fn make_dynamic_import<'a>(
-    ast: AstBuilder<'a>,
-    decl: &ImportDeclaration<'a>,
-    import_name: &'a str,
-) -> VariableDeclaration<'a> {
-    // __oxjest_import_{}__ = await import("...")
-    let await_import = ast.variable_declarator(
-        Span::default(),
-        VariableDeclarationKind::Const,
-        ast.binding_pattern(
-            ast.binding_pattern_kind_binding_identifier(Span::default(), import_name),
-            Option::<Box<'a, _>>::None,
-            false,
-        ),
-        Some(ast.expression_await(
-            Span::default(),
-            ast.expression_import(
-                Span::default(),
-                ast.expression_string_literal(decl.source.span, decl.source.value, decl.source.raw),
-                None,
-                None,
-            ),
-        )),
-        false,
-    );
-
-    // foo = __oxjest_import_{}__.foo, bar = __oxjest_import_{}__.default, ...
-    let declarations = decl.specifiers.iter().flatten().map(|specifier| {
-        ast.variable_declarator(
-            Span::default(),
-            VariableDeclarationKind::Const,
-            ast.binding_pattern(
-                BindingPatternKind::BindingIdentifier(
-                    ast.alloc(specifier.local().clone_in(ast.allocator)),
-                ),
-                Option::<Box<'a, _>>::None,
-                false,
-            ),
-            Some(match specifier {
-                ImportDeclarationSpecifier::ImportNamespaceSpecifier(_) => {
-                    ast.expression_identifier(Span::default(), import_name)
+/// Turns the factory into `__oxjest__.normalizeFactory(factory)`, which maps the values returned in the CommonJS
+/// style, such as `jest.fn()` or `{ __esModule: true, default }`, into module namespaces.
+fn make_normalize_factory<'a>(ast: AstBuilder<'a>, factory: Expression<'a>) -> Expression<'a> {
+    make_runtime_call(ast, Span::default(), "normalizeFactory", [factory.into()])
+}
+
+/// Splits `jest.mock(a).mock(b)` into `jest.mock(a)` and `jest.mock(b)`, from the innermost call,
+/// as each of them needs to be hoisted on its own.
+fn split_jest_mock_chain<'a, State>(
+    expr: Expression<'a>,
+    ctx: &mut TraverseCtx<'a, State>,
+) -> Vec<Expression<'a>> {
+    let mut calls = Vec::new();
+    let mut expr = expr;
+    let jest = loop {
+        let Expression::CallExpression(call) = expr else {
+            // SAFETY: Checked by is_jest_mock_chain
+            unreachable!();
+        };
+        let call = call.unbox();
+        let Expression::StaticMemberExpression(callee) = call.callee else {
+            // SAFETY: Checked by is_jest_mock_chain
+            unreachable!();
+        };
+        let callee = callee.unbox();
+
+        calls.push((call.span, callee.property, call.arguments));
+        if is_jest_object(&callee.object, ctx.scoping()) {
+            break callee.object;
+        }
+
+        expr = callee.object;
+    };
+
+    calls
+        .into_iter()
+        .rev()
+        .map(|(span, property, arguments)| {
+            // References to the jest object need to be created for each call, to be bound in the same way
+            let object = match &jest {
+                Expression::Identifier(ident) => {
+                    let symbol_id = ident.reference_id.get().and_then(|reference_id| {
+                        ctx.scoping().get_reference(reference_id).symbol_id()
+                    });
+                    ctx.create_ident_expr(ident.span, ident.name, symbol_id, ReferenceFlags::Read)
                 }
-                _ => ast
-                    .member_expression_static(
-                        Span::default(),
-                        ast.expression_identifier(Span::default(), import_name),
-                        match specifier {
-                            ImportDeclarationSpecifier::ImportDefaultSpecifier(specifier) => {
-                                ast.identifier_name(specifier.span, "default")
-                            }
-                            ImportDeclarationSpecifier::ImportSpecifier(specifier) => ast
-                                .identifier_name(
-                                    specifier.imported.span(),
-                                    specifier.imported.name(),
-                                ),
-                            _ => unreachable!(),
-                        },
-                        false,
-                    )
+                jest => jest.clone_in(ctx.ast.allocator),
+            };
+
+            ctx.ast.expression_call(
+                span,
+                ctx.ast
+                    .member_expression_static(Span::default(), object, property, false)
                     .into(),
-            }),
-            false,
+                Option::<Box<'_, _>>::None,
+                arguments,
+                false,
+            )
+        })
+        .collect()
+}
+
+/// Checks that the mock options are `{ virtual: true }`.
+fn is_virtual_mock(call: &CallExpression) -> bool {
+    let Some(Argument::ObjectExpression(options)) = call.arguments.get(2) else {
+        return false;
+    };
+
+    options.properties.iter().any(|property| {
+        matches!(
+            property,
+            ObjectPropertyKind::ObjectProperty(property)
+                if property.key.is_specific_static_name("virtual")
+                    && matches!(&property.value, Expression::BooleanLiteral(lit) if lit.value)
         )
-    });
-
-    // const __oxjest_import_{}__ = await import("..."), foo = __oxjest_import_{}__.foo, ...;
-    ast.variable_declaration(
-        decl.span,
-        VariableDeclarationKind::Const,
-        ast.vec_from_iter(once(await_import).chain(declarations)),
-        false,
+    })
+}
+
+/// Turns `jest.unstable_mockModule(id, factory, options)` into `__oxjest__.<method>(jest, id, factory)`,
+/// registering the mock through the runtime module.
+/// Mocks are registered by `mockModule` to restore them after importing the actual modules, and virtual mocks are
+/// registered by `mockVirtualModule` as they can't be resolved in ESM by themselves.
+fn make_runtime_mock<'a>(
+    ast: AstBuilder<'a>,
+    call: &mut CallExpression<'a>,
+    method: &'static str,
+) -> Expression<'a> {
+    let Expression::StaticMemberExpression(member) = &mut call.callee else {
+        unreachable!();
+    };
+
+    // Comments should stay at the call, rather than moving into the arguments
+    let mut jest = member.object.take_in(ast.allocator);
+    *jest.span_mut() = Span::default();
+
+    let arguments = call.arguments.drain(..2);
+
+    make_runtime_call(
+        ast,
+        call.span,
+        method,
+        once(Argument::from(jest)).chain(arguments),
     )
 }
 
+/// Where the mock calls are hoisted to, following babel-plugin-jest-hoist.
+enum HoistTo {
+    /// The top of the module, which requires the imports to be dynamic.
+    Program,
+    /// The top of the enclosing block, such as a function body.
+    Block,
+    /// The mock call is not a statement in a block, thus can't be hoisted.
+    Nowhere,
+}
+
+impl HoistTo {
+    fn from_ctx<State>(ctx: &TraverseCtx<'_, State>) -> Self {
+        if !matches!(ctx.parent(), Ancestor::ExpressionStatementExpression(_)) {
+            return Self::Nowhere;
+        }
+
+        match ctx.ancestor(1) {
+            Ancestor::ProgramBody(_) => Self::Program,
+            Ancestor::BlockStatementBody(_)
+            | Ancestor::FunctionBodyStatements(_)
+            | Ancestor::StaticBlockBody(_) => Self::Block,
+            _ => Self::Nowhere,
+        }
+    }
+}
+
 pub(crate) struct ConvertMocks<'a> {
+    /// Mocks to be hoisted to the top of the module.
     mocks: Vec<Expression<'a>>,
+    /// Mocks to be hoisted to the top of the enclosing blocks, from the outermost one.
+    block_mocks: Vec<Vec<Expression<'a>>>,
+    /// Variables referenced by the factories of the mocks hoisted to the top of the module.
+    mock_variables: Vec<SymbolId>,
+    /// Modules mocked or unmocked by the mocks hoisted to the top of the module.
+    mocked_modules: Vec<(Atom<'a>, Span)>,
+    /// Modules imported by dynamic imports, `require` or `jest.requireMock`, or `None` if not known statically.
+    loaded_modules: Vec<Option<Atom<'a>>>,
+    /// Whether to rewrite references to the imports converted into dynamic imports, to keep their live bindings.
+    live_bindings: bool,
+    /// Graph of the imported modules, to convert only the imports that may reach the mocked modules.
+    module_graph: Option<&'a ModuleGraph<'a>>,
+    /// Whether to convert all imports even if the module graph is available.
+    convert_all_imports: bool,
+    pub(crate) diagnostics: Vec<OxcDiagnostic>,
 }
 
-impl ConvertMocks<'_> {
+impl<'a> ConvertMocks<'a> {
     pub(crate) fn new() -> Self {
-        Self { mocks: Vec::new() }
+        Self {
+            mocks: Vec::new(),
+            block_mocks: Vec::new(),
+            mock_variables: Vec::new(),
+            mocked_modules: Vec::new(),
+            loaded_modules: Vec::new(),
+            live_bindings: true,
+            module_graph: None,
+            convert_all_imports: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_live_bindings(mut self, live_bindings: bool) -> Self {
+        self.live_bindings = live_bindings;
+        self
+    }
+
+    pub(crate) fn with_module_graph(mut self, module_graph: Option<&'a ModuleGraph<'a>>) -> Self {
+        self.module_graph = module_graph;
+        self
+    }
+
+    pub(crate) fn with_convert_all_imports(mut self, convert_all_imports: bool) -> Self {
+        self.convert_all_imports = convert_all_imports;
+        self
+    }
+
+    /// Reports the module mocked by the call if it's not resolvable.
+    fn check_mocked_module(&mut self, call: &CallExpression<'a>) {
+        if let Some(module_graph) = self.module_graph
+            && let Some(Argument::StringLiteral(lit)) = call.arguments.first()
+        {
+            self.diagnostics
+                .extend(module_graph.check_resolvable(&lit.value, lit.span));
+        }
+    }
+
+    /// Records the module mocked by the call, which is hoisted to the top of the module.
+    fn record_mocked_module(&mut self, call: &CallExpression<'a>) {
+        if let Some(Argument::StringLiteral(lit)) = call.arguments.first() {
+            self.mocked_modules.push((lit.value, lit.span));
+        }
+    }
+
+    /// Records the module loaded by the expression other than static imports, such as `import("...")`.
+    fn record_loaded_module(&mut self, expr: &Expression<'a>, scoping: &Scoping) {
+        let source = match expr {
+            Expression::ImportExpression(import) => &import.source,
+            Expression::CallExpression(call)
+                if is_require_call(call, scoping) || is_jest_require_mock_call(call, scoping) =>
+            {
+                match call.arguments.first().and_then(Argument::as_expression) {
+                    Some(source) => source,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        self.loaded_modules.push(match source {
+            Expression::StringLiteral(lit) => Some(lit.value),
+            _ => None,
+        });
+    }
+
+    /// Reports the hoisted mocks of the modules that are never imported by the module, even indirectly,
+    /// as they have no effect.
+    fn check_unused_mocks(&mut self, body: &[Statement<'a>]) {
+        let Some(module_graph) = self.module_graph else {
+            return;
+        };
+
+        let static_sources = body.iter().filter_map(|stmt| match stmt {
+            Statement::ImportDeclaration(decl) => Some(Some(decl.source.value)),
+            Statement::ExportNamedDeclaration(decl) => {
+                decl.source.as_ref().map(|source| Some(source.value))
+            }
+            Statement::ExportAllDeclaration(decl) => Some(Some(decl.source.value)),
+            _ => None,
+        });
+        // Any module may be imported by the dynamic imports of unknown specifiers
+        let Some(sources) = static_sources
+            .chain(self.loaded_modules.iter().copied())
+            .collect::<Option<Vec<_>>>()
+        else {
+            return;
+        };
+
+        for (specifier, span) in &self.mocked_modules {
+            let Some(resolved) = module_graph.resolve(specifier) else {
+                continue;
+            };
+
+            let targets = HashSet::from([resolved]);
+            if sources
+                .iter()
+                .any(|source| module_graph.reaches(source, &targets))
+            {
+                continue;
+            }
+
+            self.diagnostics.push(
+                OxcDiagnostic::warn(format!(
+                    "The module `{specifier}` is never imported by this module, thus the mock has no effect."
+                ))
+                .with_label(span.label("Never imported"))
+                .with_help("Remove the mock, or import the module or the modules importing it."),
+            );
+        }
+    }
+
+    /// Resolves the modules mocked by the hoisted mocks, or returns `None` if any of them can't be resolved.
+    fn resolve_mocked_modules(&self) -> Option<HashSet<String>> {
+        let module_graph = self.module_graph.filter(|_| !self.convert_all_imports)?;
+
+        self.mocked_modules
+            .iter()
+            .map(|(specifier, _)| module_graph.resolve(specifier))
+            .collect()
+    }
+
+    /// Reports variables referenced by the mock factory that may not be initialized yet, as the factory is hoisted.
+    fn check_factory(&mut self, call: &CallExpression<'a>, hoist_to: &HoistTo, scoping: &Scoping) {
+        let Some(factory) = call.arguments.get(1).and_then(Argument::as_expression) else {
+            return;
+        };
+
+        let references = FactoryReferences::collect(scoping, factory);
+
+        self.diagnostics
+            .extend(references.illegal.into_iter().map(|(name, span)| {
+                OxcDiagnostic::error(
+                    "The module factory of a hoisted mock is not allowed to reference any out-of-scope variables.",
+                )
+                .with_label(span.label(format!("Invalid variable access: {name}")))
+                .with_help(
+                    "Variable names prefixed with `mock` (case insensitive) are permitted, if it is ensured that the mock is imported lazily.",
+                )
+            }));
+
+        // Factories of the mocks hoisted to the top of the module are called by the dynamic imports,
+        // which are evaluated before the variables are initialized
+        if matches!(hoist_to, HoistTo::Program) {
+            self.mock_variables.extend(references.eager);
+        }
+    }
+
+    fn hoist(&mut self, node: &mut Expression<'a>, hoist_to: HoistTo, ast: AstBuilder<'a>) {
+        match hoist_to {
+            HoistTo::Program => self.mocks.push(node.take_in(ast.allocator)),
+            HoistTo::Block => {
+                // SAFETY: Blocks are always entered before the statements in them
+                let block_mocks = self.block_mocks.last_mut().unwrap();
+                block_mocks.push(node.take_in(ast.allocator));
+            }
+            HoistTo::Nowhere => {}
+        }
     }
 }
 
 impl<'a, State> Traverse<'a, State> for ConvertMocks<'a> {
     fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
-        // Insert hoisted mocks at the top of the body
+        let variables = take_variables(
+            &mut node.body,
+            &self.mock_variables,
+            ctx.scoping(),
+            ctx.ast,
+            &mut self.diagnostics,
+        );
+
+        // Insert hoisted mocks at the top of the body, after the variables they depend on
         node.body.splice(
             0..0,
-            self.mocks.iter().map(|mock_call| {
-                ctx.ast
-                    .statement_expression(Span::default(), mock_call.clone_in(ctx.ast.allocator))
-            }),
+            variables
+                .into_iter()
+                .chain(self.mocks.iter().map(|mock_call| {
+                    ctx.ast.statement_expression(
+                        Span::default(),
+                        mock_call.clone_in(ctx.ast.allocator),
+                    )
+                })),
         );
 
+        self.check_unused_mocks(&node.body);
+
         // Imports don't need to be turned into dynamic imports if there are no mocks
         if !self.mocks.is_empty() {
-            let import_id = AtomicUsize::new(0);
+            let mut dynamic_imports = DynamicImports::new(ctx.ast);
+            let local_exports = collect_local_exports(&node.body, ctx.scoping());
+            let mut export_names = collect_export_names(&node.body);
+            let mut live_bindings = LiveBindings::new(ctx.ast, ctx.scoping());
 
-            node.body.iter_mut().for_each(|stmt| {
-                let Statement::ImportDeclaration(decl) = stmt else {
-                    return;
+            let mocked_modules = self.resolve_mocked_modules();
+            let body = node.body.take_in(ctx.ast.allocator);
+            for stmt in body {
+                // Only the modules that may reach the mocked modules need to be imported after the mocks
+                let source = match &stmt {
+                    Statement::ImportDeclaration(decl) => Some(&decl.source),
+                    Statement::ExportNamedDeclaration(decl) => decl.source.as_ref(),
+                    Statement::ExportAllDeclaration(decl) => Some(&decl.source),
+                    _ => None,
                 };
+                if let Some(source) = source
+                    && let Some(module_graph) = self.module_graph
+                    && let Some(mocked_modules) = &mocked_modules
+                    && !module_graph.reaches(&source.value, mocked_modules)
+                {
+                    node.body.push(stmt);
+                    continue;
+                }
+
+                match stmt {
+                    Statement::ImportDeclaration(decl)
+                        if decl.phase == Some(ImportPhase::Source) =>
+                    {
+                        // The source of a module can't be mocked, and doesn't depend on the mocks
+                        self.diagnostics.push(
+                            OxcDiagnostic::error(
+                                "Source phase imports can't be converted into dynamic imports.",
+                            )
+                            .with_label(decl.span.label("Imported in source phase"))
+                            .with_help("Import the module in source phase from a module without hoisted mocks."),
+                        );
+                        node.body.push(Statement::ImportDeclaration(decl));
+                    }
+                    Statement::ImportDeclaration(decl) => {
+                        let (import_name, await_import) = dynamic_imports.namespace(
+                            &decl.source,
+                            decl.with_clause.as_deref(),
+                            decl.phase,
+                        );
+
+                        // Re-exported bindings still need to be declared, which can't be live
+                        if self.live_bindings {
+                            decl.specifiers
+                                .iter()
+                                .flatten()
+                                .filter(|specifier| {
+                                    specifier
+                                        .local()
+                                        .symbol_id
+                                        .get()
+                                        .is_none_or(|symbol_id| !local_exports.contains(&symbol_id))
+                                })
+                                .for_each(|specifier| {
+                                    live_bindings.insert(specifier, Atom::from(import_name));
+                                });
+                        }
+
+                        node.body.extend(dynamic_imports.convert_import(
+                            &decl,
+                            import_name,
+                            await_import,
+                            &live_bindings,
+                        ));
+                    }
+                    Statement::ExportNamedDeclaration(decl) if decl.source.is_some() => {
+                        let Some(source) = &decl.source else {
+                            unreachable!();
+                        };
 
-                let import_id = import_id.fetch_add(1, Ordering::Relaxed);
-                let import_name = format!("__oxjest_import_{import_id}__");
+                        node.body
+                            .extend(dynamic_imports.convert_reexport(&decl, source));
+                    }
+                    Statement::ExportAllDeclaration(decl) if decl.exported.is_some() => {
+                        let Some(exported) = &decl.exported else {
+                            unreachable!();
+                        };
 
-                *stmt = Statement::VariableDeclaration(ctx.ast.alloc(make_dynamic_import(
-                    ctx.ast,
-                    decl,
-                    ctx.ast.str(&import_name),
-                )));
-            })
+                        node.body
+                            .extend(dynamic_imports.convert_namespace_reexport(&decl, exported));
+                    }
+                    Statement::ExportAllDeclaration(decl) => {
+                        // The names to re-export are known only by reading the module
+                        let Some(names) = self
+                            .module_graph
+                            .and_then(|module_graph| module_graph.star_exports(&decl.source.value))
+                        else {
+                            self.diagnostics.push(
+                                OxcDiagnostic::warn(
+                                    "`export * from` can't be converted into a dynamic import without knowing the exported names, thus it's evaluated before the hoisted mocks.",
+                                )
+                                .with_label(decl.span.label("Re-exported statically"))
+                                .with_help("Re-export the names explicitly, such as `export { foo } from`."),
+                            );
+                            node.body.push(Statement::ExportAllDeclaration(decl));
+                            continue;
+                        };
+
+                        let names = names
+                            .into_iter()
+                            .filter(|name| export_names.insert(name.clone()))
+                            .collect::<Vec<_>>();
+                        node.body
+                            .extend(dynamic_imports.convert_star_reexport(&decl, &names));
+                    }
+                    stmt => node.body.push(stmt),
+                }
+            }
+
+            live_bindings.visit_statements(&mut node.body);
         }
     }
 
     fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.record_loaded_module(node, ctx.scoping());
+
         let Expression::CallExpression(call) = node else {
             return;
         };
 
-        let is_jest_mock_call = is_jest_mock_call(call);
-        let is_jest_do_mock_call = is_jest_do_mock_call(call);
-        if !is_jest_mock_call && !is_jest_do_mock_call {
+        // Calls with the specifiers unknown statically are left untransformed, as reported by InlineSpecifiers
+        if !matches!(call.arguments.first(), Some(Argument::StringLiteral(_))) {
+            return;
+        }
+
+        // jest.unmock and jest.dontMock are converted the same way, except for hoisting
+        let is_jest_unmock_call = is_jest_unmock_call(call, ctx.scoping());
+        if is_jest_unmock_call || is_jest_dont_mock_call(call, ctx.scoping()) {
+            let Expression::StaticMemberExpression(member) = &mut call.callee else {
+                // SAFETY: Already checked above
+                unreachable!();
+            };
+
+            member.property.name = ctx.ast.atom("unstable_unmockModule");
+            self.check_mocked_module(call);
+
+            if is_jest_unmock_call {
+                let hoist_to = HoistTo::from_ctx(ctx);
+                if matches!(hoist_to, HoistTo::Program) {
+                    self.record_mocked_module(call);
+                }
+
+                self.hoist(node, hoist_to, ctx.ast);
+            }
+
+            return;
+        }
+
+        let is_jest_mock_call = is_jest_mock_call(call, ctx.scoping());
+        let is_jest_do_mock_call = is_jest_do_mock_call(call, ctx.scoping());
+        let is_jest_unstable_mock_module_call =
+            is_jest_unstable_mock_module_call(call, ctx.scoping());
+        if !is_jest_mock_call && !is_jest_do_mock_call && !is_jest_unstable_mock_module_call {
             return;
         }
 
@@ -174,36 +560,133 @@ impl<'a, State> Traverse<'a, State> for ConvertMocks<'a> {
 
         member.property.name = ctx.ast.atom("unstable_mockModule");
 
+        // Factories of jest.mock and jest.doMock are written for CommonJS, unlike jest.unstable_mockModule
+        let has_factory = call.arguments.len() >= 2;
         if call.arguments.len() < 2 {
             let Some(Argument::StringLiteral(lit)) = call.arguments.first() else {
-                return;
+                // SAFETY: Already checked above
+                unreachable!();
             };
 
-            let id = lit.value.as_str();
+            let Expression::StaticMemberExpression(member) = &call.callee else {
+                // SAFETY: Already checked above
+                unreachable!();
+            };
+
+            // Comments should stay at the call, rather than moving into the arguments
+            let mut jest = member.object.clone_in(ctx.ast.allocator);
+            *jest.span_mut() = Span::default();
+
+            let factory = make_create_mock_factory(ctx.ast, jest, lit);
+            call.arguments.push(factory.into());
+        }
+
+        // only jest.mock needs to be hoisted, while jest.unstable_mockModule is hoisted only if it's
+        // written at the top level, as it's also used like jest.doMock inside tests
+        let hoist_to = HoistTo::from_ctx(ctx);
+        let is_hoisted = match hoist_to {
+            HoistTo::Program => is_jest_mock_call || is_jest_unstable_mock_module_call,
+            HoistTo::Block => is_jest_mock_call,
+            HoistTo::Nowhere => false,
+        };
+
+        if is_hoisted {
+            self.check_factory(call, &hoist_to, ctx.scoping());
+        }
 
-            call.arguments
-                .push(make_create_mock_factory(ctx.ast, id).into())
+        // Virtual modules are never imported by other modules, nor resolvable
+        let is_virtual_mock = is_virtual_mock(call);
+        if !is_virtual_mock {
+            self.check_mocked_module(call);
         }
 
-        // only jest.mock needs to be hoisted
-        if is_jest_mock_call {
-            self.mocks.push(node.take_in(ctx.ast.allocator));
+        if is_hoisted && !is_virtual_mock && matches!(hoist_to, HoistTo::Program) {
+            self.record_mocked_module(call);
+        }
+
+        // Virtual mocks are normalized by the runtime, as the factory is registered for CommonJS too
+        if has_factory
+            && !is_virtual_mock
+            && !is_jest_unstable_mock_module_call
+            && let Some(factory) = call
+                .arguments
+                .get_mut(1)
+                .and_then(Argument::as_expression_mut)
+        {
+            *factory = make_normalize_factory(ctx.ast, factory.take_in(ctx.ast.allocator));
+        }
+
+        *node = if is_virtual_mock {
+            make_runtime_mock(ctx.ast, call, "mockVirtualModule")
+        } else {
+            make_runtime_mock(ctx.ast, call, "mockModule")
+        };
+
+        if is_hoisted {
+            self.hoist(node, hoist_to, ctx.ast);
+        }
+    }
+
+    fn enter_statements(
+        &mut self,
+        node: &mut ArenaVec<'a, Statement<'a>>,
+        ctx: &mut TraverseCtx<'a, State>,
+    ) {
+        self.block_mocks.push(Vec::new());
+
+        // Chained mocks are split into statements before their calls are visited
+        let is_chained = |stmt: &Statement<'a>| matches!(stmt, Statement::ExpressionStatement(stmt) if is_jest_mock_chain(&stmt.expression, ctx.scoping()));
+        if !node.iter().any(is_chained) {
+            return;
+        }
+
+        let body = node.take_in(ctx.ast.allocator);
+        for stmt in body {
+            let Statement::ExpressionStatement(mut stmt) = stmt else {
+                node.push(stmt);
+                continue;
+            };
+            if !is_jest_mock_chain(&stmt.expression, ctx.scoping()) {
+                node.push(Statement::ExpressionStatement(stmt));
+                continue;
+            }
+
+            let calls = split_jest_mock_chain(stmt.expression.take_in(ctx.ast.allocator), ctx);
+            node.extend(calls.into_iter().enumerate().map(|(index, call)| {
+                // Comments of the statement should stay at the first call
+                let span = if index == 0 {
+                    stmt.span
+                } else {
+                    Span::default()
+                };
+                ctx.ast.statement_expression(span, call)
+            }));
         }
     }
 
     fn exit_statements(
         &mut self,
         node: &mut ArenaVec<'a, Statement<'a>>,
-        _ctx: &mut TraverseCtx<'a, State>,
+        ctx: &mut TraverseCtx<'a, State>,
     ) {
         node.retain(|stmt| !matches!(stmt, Statement::ExpressionStatement(stmt) if stmt.expression.is_null()));
+
+        // Insert mocks hoisted within this block at the top of it
+        // SAFETY: Blocks are always entered before exiting
+        let block_mocks = self.block_mocks.pop().unwrap();
+        node.splice(
+            0..0,
+            block_mocks
+                .into_iter()
+                .map(|mock_call| ctx.ast.statement_expression(Span::default(), mock_call)),
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testing::transform;
+    use crate::testing::{fixture_path, format_errors, resolve, transform, transform_with};
     use oxc::allocator::Allocator;
 
     #[test]
@@ -221,8 +704,31 @@ mod tests {
         let code = transform(&allocator, source_text, ConvertMocks::new());
 
         insta::assert_snapshot!(code, @r#"
-        jest.unstable_mockModule("./greeter.js", () => ({ greet: () => "Hello, world!" }));
-        const __oxjest_import_0__ = await import("./greeter.js"), greet = __oxjest_import_0__.greet;
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({ greet: () => "Hello, world!" })));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        "#);
+    }
+
+    #[test]
+    fn test_mock_local_jest() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        // a local binding named `jest` is not the jest object
+        function helper(jest) {
+            jest.mock("./greeter.js");
+        }
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import { greet } from "./greeter.js";
+        // a local binding named `jest` is not the jest object
+        function helper(jest) {
+        	jest.mock("./greeter.js");
+        }
         "#);
     }
 
@@ -239,8 +745,667 @@ mod tests {
         let code = transform(&allocator, source_text, ConvertMocks::new());
 
         insta::assert_snapshot!(code, @r#"
-        jest.unstable_mockModule("./greeter.js", __oxjest__.createMockFactory(await import("./greeter.js")));
-        const __oxjest_import_0__ = await import("./greeter.js"), greet = __oxjest_import_0__.greet;
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        "#);
+    }
+
+    #[test]
+    fn test_unstable_mock_module() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        // this mocking needs to be hoisted to the top of this module
+        jest.unstable_mockModule("./greeter.js", () => ({
+          greet: () => "Hello, world!",
+        }));
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", () => ({ greet: () => "Hello, world!" }));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        "#);
+    }
+
+    #[test]
+    fn test_unstable_mock_module_nested() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        it("greets", async () => {
+          // this mocking does not need to be hoisted
+          jest.unstable_mockModule("./greeter.js", () => ({
+            greet: () => "Hello, world!",
+          }));
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import { greet } from "./greeter.js";
+        it("greets", async () => {
+        	// this mocking does not need to be hoisted
+        	__oxjest__.mockModule(jest, "./greeter.js", () => ({ greet: () => "Hello, world!" }));
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_mock_virtual() {
+        let source_text = r#"
+        import { greet } from "virtual-greeter";
+
+        // this mocking needs to be registered as a virtual module
+        jest.mock("virtual-greeter", () => ({
+          greet: () => "Hello, world!",
+        }), { virtual: true });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockVirtualModule(jest, "virtual-greeter", () => ({ greet: () => "Hello, world!" }));
+        const __oxjest_import_0__ = await import("virtual-greeter");
+        "#);
+    }
+
+    #[test]
+    fn test_mock_nested() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        describe("greeter", () => {
+          const mockMessage = "Hello, world!";
+
+          // this mocking needs to be hoisted to the top of this block
+          jest.mock("./greeter.js", () => ({
+            greet: () => mockMessage,
+          }));
+
+          if (process.env.CI) {
+            console.log("CI");
+            jest.mock("./logger.js");
+          }
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import { greet } from "./greeter.js";
+        describe("greeter", () => {
+        	__oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({ greet: () => mockMessage })));
+        	const mockMessage = "Hello, world!";
+        	if (process.env.CI) {
+        		__oxjest__.mockModule(jest, "./logger.js", __oxjest__.createMockFactory(jest, "./logger.js", () => import("./logger.js")));
+        		console.log("CI");
+        	}
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_mock_out_of_scope_variables() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        const message = "Hello, world!";
+        const mockGreet = () => message;
+
+        jest.mock("./greeter.js", () => {
+          const name = "world";
+
+          return {
+            greet: () => `${message}, ${name}`,
+            mockGreet,
+            log: console.log,
+          };
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let (_, convert_mocks) = transform_with(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        The module factory of a hoisted mock is not allowed to reference any out-of-scope variables.
+          at 253..260: Invalid variable access: message
+        "#);
+    }
+
+    #[test]
+    fn test_mock_variables() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        const mockMessage = "Hello, world!", other = 1;
+        const mockGreet = jest.fn().mockReturnValue(mockMessage);
+
+        jest.mock("./greeter.js", () => ({ greet: mockGreet }));
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const mockMessage = "Hello, world!";
+        const mockGreet = jest.fn().mockReturnValue(mockMessage);
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({ greet: mockGreet })));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        const other = 1;
+        "#);
+    }
+
+    #[test]
+    fn test_mock_variables_not_hoistable() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        const mockGreet = createGreet();
+        const mockLog = jest.fn();
+
+        jest.mock("./greeter.js", () => ({
+          greet: mockGreet,
+          log: (...args) => mockLog(...args),
+        }));
+        "#;
+
+        let allocator = Allocator::new();
+        let (code, convert_mocks) = transform_with(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({
+        	greet: mockGreet,
+        	log: (...args) => mockLog(...args)
+        })));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        const mockGreet = createGreet();
+        const mockLog = jest.fn();
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        `mockGreet` is referenced by a hoisted mock factory, but can't be hoisted as it may have side effects.
+          at 62..71: Declared here
+        "#);
+    }
+
+    #[test]
+    fn test_mock_variables_circular_not_hoistable() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        const mockA = jest.fn(mockB, laterConst);
+        const mockB = jest.fn(mockA);
+        const laterConst = createThing();
+
+        jest.mock("./greeter.js", () => ({ a: mockA, b: mockB }));
+        "#;
+
+        let allocator = Allocator::new();
+        let (code, convert_mocks) = transform_with(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({
+        	a: mockA,
+        	b: mockB
+        })));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        const mockA = jest.fn(mockB, laterConst);
+        const mockB = jest.fn(mockA);
+        const laterConst = createThing();
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        `mockA` is referenced by a hoisted mock factory, but can't be hoisted as it may have side effects.
+          at 62..67: Declared here
+        `mockB` is referenced by a hoisted mock factory, but can't be hoisted as it may have side effects.
+          at 112..117: Declared here
+        "#);
+    }
+
+    #[test]
+    fn test_mock_live_bindings() {
+        let source_text = r#"
+        import greeter, { count, greet as hello, "kebab-case" as kebab } from "./greeter.js";
+        import * as logger from "./logger.js";
+
+        jest.mock("./greeter.js");
+
+        hello`world`;
+        logger.log(greeter(), kebab(), { count });
+
+        export { count };
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        const __oxjest_import_0__ = await import("./greeter.js"), count = __oxjest_import_0__.count;
+        const __oxjest_import_1__ = await import("./logger.js"), logger = __oxjest_import_1__;
+        (0, __oxjest_import_0__.greet)`world`;
+        logger.log((0, __oxjest_import_0__.default)(), (0, __oxjest_import_0__["kebab-case"])(), { count });
+        export { count };
+        "#);
+    }
+
+    #[test]
+    fn test_mock_without_live_bindings() {
+        let source_text = r#"
+        import { greet, "kebab-case" as kebab } from "./greeter.js";
+
+        jest.mock("./greeter.js");
+
+        greet(kebab);
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_live_bindings(false),
+        );
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        const __oxjest_import_0__ = await import("./greeter.js"), greet = __oxjest_import_0__.greet, kebab = __oxjest_import_0__["kebab-case"];
+        greet(kebab);
+        "#);
+    }
+
+    #[test]
+    fn test_mock_reexports() {
+        let source_text = r#"
+        export { greet, default as greeter, "kebab-case" as kebabCase } from "./greeter.js";
+        export * as logger from "./logger.js";
+        export * from "./utils.js";
+
+        jest.mock("./greeter.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let (code, convert_mocks) = transform_with(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        const __oxjest_import_0__ = await import("./greeter.js"), __oxjest_export_0__ = __oxjest_import_0__.greet, __oxjest_export_1__ = __oxjest_import_0__.default, __oxjest_export_2__ = __oxjest_import_0__["kebab-case"];
+        export { __oxjest_export_0__ as greet, __oxjest_export_1__ as greeter, __oxjest_export_2__ as kebabCase };
+        const __oxjest_import_1__ = await import("./logger.js");
+        export { __oxjest_import_1__ as logger };
+        export * from "./utils.js";
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        `export * from` can't be converted into a dynamic import without knowing the exported names, thus it's evaluated before the hoisted mocks.
+          at 149..176: Re-exported statically
+        "#);
+    }
+
+    #[test]
+    fn test_mock_import_attributes() {
+        let source_text = r#"
+        import data from "./data.json" with { type: "json" };
+        import defer * as logger from "./logger.js";
+        export { default as config } from "./config.json" with { "type": "json" };
+
+        jest.mock("./logger.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./logger.js", __oxjest__.createMockFactory(jest, "./logger.js", () => import("./logger.js")));
+        const __oxjest_import_0__ = await import("./data.json", { with: { type: "json" } });
+        const __oxjest_import_1__ = await import.defer("./logger.js"), logger = __oxjest_import_1__;
+        const __oxjest_import_2__ = await import("./config.json", { with: { "type": "json" } }), __oxjest_export_0__ = __oxjest_import_2__.default;
+        export { __oxjest_export_0__ as config };
+        "#);
+    }
+
+    #[test]
+    fn test_mock_duplicate_imports() {
+        let source_text = r#"
+        import greet from "./greeter.js";
+        import { greetLater } from "./greeter.js";
+        import * as greeter from "./greeter.js";
+        import data from "./data.json" with { type: "json" };
+        import rawData from "./data.json" with { type: "text" };
+        export { greetLater as later } from "./greeter.js";
+
+        jest.mock("./greeter.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        const greeter = __oxjest_import_0__;
+        const __oxjest_import_1__ = await import("./data.json", { with: { type: "json" } });
+        const __oxjest_import_2__ = await import("./data.json", { with: { type: "text" } });
+        const __oxjest_export_0__ = __oxjest_import_0__.greetLater;
+        export { __oxjest_export_0__ as later };
+        "#);
+    }
+
+    #[test]
+    fn test_mock_chained() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        // these mockings need to be hoisted one by one
+        jest.mock("./greeter.js", () => ({ greet: jest.fn() })).unmock("./counter.js").mock("./logger.js");
+
+        describe("greeter", () => {
+            jest.doMock("./greeter.js").mock("./counter.js");
+        });
+
+        // these calls are not chained on the jest object
+        jest.fn().mockReturnValue(1);
+        jest.mock("./greeter.js").resetModules();
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({ greet: jest.fn() })));
+        jest.unstable_unmockModule("./counter.js");
+        __oxjest__.mockModule(jest, "./logger.js", __oxjest__.createMockFactory(jest, "./logger.js", () => import("./logger.js")));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        describe("greeter", () => {
+        	__oxjest__.mockModule(jest, "./counter.js", __oxjest__.createMockFactory(jest, "./counter.js", () => import("./counter.js")));
+        	__oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        });
+        // these calls are not chained on the jest object
+        jest.fn().mockReturnValue(1);
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js"))).resetModules();
+        "#);
+    }
+
+    #[test]
+    fn test_mock_source_phase_import() {
+        let source_text = r#"
+        import source wasm from "./module.wasm";
+
+        jest.mock("./logger.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let (code, convert_mocks) = transform_with(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./logger.js", __oxjest__.createMockFactory(jest, "./logger.js", () => import("./logger.js")));
+        import source wasm from "./module.wasm";
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        Source phase imports can't be converted into dynamic imports.
+          at 9..49: Imported in source phase
+        "#);
+    }
+
+    #[test]
+    fn test_mock_module_graph() {
+        let source_text = r#"
+        import { greet } from "./greeter.ts";
+        import { report } from "./counterReporter.ts";
+        export { count } from "./counter.ts";
+
+        jest.mock("./counter.ts");
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let code = transform(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_module_graph(Some(&module_graph)),
+        );
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./counter.ts", __oxjest__.createMockFactory(jest, "./counter.ts", () => import("./counter.ts")));
+        import { greet } from "./greeter.ts";
+        const __oxjest_import_0__ = await import("./counterReporter.ts");
+        const __oxjest_import_1__ = await import("./counter.ts"), __oxjest_export_0__ = __oxjest_import_1__.count;
+        export { __oxjest_export_0__ as count };
+        "#);
+    }
+
+    #[test]
+    fn test_mock_module_graph_star_reexports() {
+        let source_text = r#"
+        export * from "./starExports.ts";
+        export * from "./commonjsGreeter.cjs";
+        export function greet() {}
+
+        jest.mock("./greeter.ts");
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let (code, convert_mocks) = transform_with(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_module_graph(Some(&module_graph)),
+        );
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./greeter.ts", __oxjest__.createMockFactory(jest, "./greeter.ts", () => import("./greeter.ts")));
+        const __oxjest_import_0__ = await import("./starExports.ts"), __oxjest_export_0__ = __oxjest_import_0__.counter, __oxjest_export_1__ = __oxjest_import_0__.Greeter;
+        export { __oxjest_export_0__ as counter, __oxjest_export_1__ as Greeter };
+        export * from "./commonjsGreeter.cjs";
+        export function greet() {}
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#""#);
+    }
+
+    #[test]
+    fn test_mock_module_graph_convert_all_imports() {
+        let source_text = r#"
+        import { greet } from "./greeter.ts";
+
+        jest.mock("./counter.ts");
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let code = transform(
+            &allocator,
+            source_text,
+            ConvertMocks::new()
+                .with_module_graph(Some(&module_graph))
+                .with_convert_all_imports(true),
+        );
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./counter.ts", __oxjest__.createMockFactory(jest, "./counter.ts", () => import("./counter.ts")));
+        const __oxjest_import_0__ = await import("./greeter.ts");
+        "#);
+    }
+
+    #[test]
+    fn test_mock_unresolvable() {
+        let source_text = r#"
+        import { greet } from "./greeter.ts";
+
+        jest.mock("./missing.ts", () => ({}));
+        jest.mock("virtual-module", () => ({}), { virtual: true });
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let (code, convert_mocks) = transform_with(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_module_graph(Some(&module_graph)),
+        );
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./missing.ts", __oxjest__.normalizeFactory(() => ({})));
+        __oxjest__.mockVirtualModule(jest, "virtual-module", () => ({}));
+        const __oxjest_import_0__ = await import("./greeter.ts");
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        Cannot resolve module `./missing.ts`.
+          at 66..80: Not resolvable
+        "#);
+    }
+
+    #[test]
+    fn test_mock_never_imported() {
+        let source_text = r#"
+        import { greet } from "./greeter.ts";
+
+        jest.mock("./counter.ts");
+        jest.mock("./defaultGreeter.ts");
+        jest.mock("./counterReporter.ts");
+        jest.mock("./ping.ts");
+
+        test("report", async () => {
+            const { report } = await import("./counterReporter.ts");
+            const ping = require("./ping.ts");
+        });
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let (_, convert_mocks) = transform_with(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_module_graph(Some(&module_graph)),
+        );
+
+        insta::assert_snapshot!(format_errors(&convert_mocks.diagnostics), @r#"
+        The module `./defaultGreeter.ts` is never imported by this module, thus the mock has no effect.
+          at 101..122: Never imported
+        "#);
+    }
+
+    #[test]
+    fn test_mock_imported_lazily() {
+        let source_text = r#"
+        import { lazyGreet } from "./lazyGreeter.ts";
+        import { load } from "./lazyLoader.ts";
+
+        jest.mock("./defaultGreeter.ts");
+        jest.mock("./counter.ts");
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let (_, convert_mocks) = transform_with(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_module_graph(Some(&module_graph)),
+        );
+
+        assert!(convert_mocks.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_mock_never_imported_unknown() {
+        let source_text = r#"
+        jest.mock("./counter.ts");
+
+        test("load", async () => {
+            await import(process.env.MODULE);
+        });
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let (_, convert_mocks) = transform_with(
+            &allocator,
+            source_text,
+            ConvertMocks::new().with_module_graph(Some(&module_graph)),
+        );
+
+        assert!(convert_mocks.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_mock_dynamic_specifier() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        let greeterPath = "./greeter.js";
+        jest.mock(greeterPath, () => ({ greet: jest.fn() }));
+        jest.mock("./logger.js").mock(greeterPath);
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        __oxjest__.mockModule(jest, "./logger.js", __oxjest__.createMockFactory(jest, "./logger.js", () => import("./logger.js")));
+        const __oxjest_import_0__ = await import("./greeter.js");
+        let greeterPath = "./greeter.js";
+        jest.mock(greeterPath, () => ({ greet: jest.fn() }));
+        jest.mock(greeterPath);
+        "#);
+    }
+
+    #[test]
+    fn test_mock_not_hoistable() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        // these mockings can't be hoisted
+        const mocked = jest.mock("./greeter.js");
+        if (process.env.CI) jest.mock("./logger.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import { greet } from "./greeter.js";
+        // these mockings can't be hoisted
+        const mocked = __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
+        if (process.env.CI) __oxjest__.mockModule(jest, "./logger.js", __oxjest__.createMockFactory(jest, "./logger.js", () => import("./logger.js")));
+        "#);
+    }
+
+    #[test]
+    fn test_unmock() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        // this unmocking needs to be hoisted to the top of this module
+        jest.unmock("./greeter.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        jest.unstable_unmockModule("./greeter.js");
+        const __oxjest_import_0__ = await import("./greeter.js");
+        "#);
+    }
+
+    #[test]
+    fn test_dont_mock() {
+        let source_text = r#"
+        import { greet } from "./greeter.js";
+
+        // this unmocking does not need to be hoisted
+        jest.dontMock("./greeter.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertMocks::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import { greet } from "./greeter.js";
+        // this unmocking does not need to be hoisted
+        jest.unstable_unmockModule("./greeter.js");
         "#);
     }
 
@@ -261,7 +1426,7 @@ mod tests {
         insta::assert_snapshot!(code, @r#"
         import { greet } from "./greeter.js";
         // this mocking does not need to be hoisted
-        jest.unstable_mockModule("./greeter.js", () => ({ greet: () => "Hello, world!" }));
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.normalizeFactory(() => ({ greet: () => "Hello, world!" })));
         "#);
     }
 
@@ -280,7 +1445,7 @@ mod tests {
         insta::assert_snapshot!(code, @r#"
         import { greet } from "./greeter.js";
         // this mocking does not need to be hoisted
-        jest.unstable_mockModule("./greeter.js", __oxjest__.createMockFactory(await import("./greeter.js")));
+        __oxjest__.mockModule(jest, "./greeter.js", __oxjest__.createMockFactory(jest, "./greeter.js", () => import("./greeter.js")));
         "#);
     }
 }
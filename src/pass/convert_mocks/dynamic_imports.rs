@@ -0,0 +1,419 @@
+use std::collections::HashSet;
+
+use oxc::allocator::{Box, CloneIn};
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{
+    BindingPatternKind, Declaration, ExportAllDeclaration, ExportNamedDeclaration, Expression,
+    ImportAttributeKey, ImportDeclaration, ImportDeclarationSpecifier, ImportOrExportKind,
+    ImportPhase, ModuleExportName, PropertyKey, PropertyKind, Span, Statement, StringLiteral,
+    VariableDeclarationKind, VariableDeclarator, WithClause,
+};
+use oxc::span::{Atom, GetSpan};
+use oxc::syntax::identifier::is_identifier_name;
+
+use crate::pass::convert_mocks::live_bindings::LiveBindings;
+
+/// Makes `{ with: { type: "json" } }` from the import attributes, for the options of dynamic imports.
+fn make_import_options<'a>(
+    ast: AstBuilder<'a>,
+    with_clause: Option<&WithClause<'a>>,
+) -> Option<Expression<'a>> {
+    let with_clause = with_clause?;
+
+    let attributes = with_clause.with_entries.iter().map(|attribute| {
+        ast.object_property_kind_object_property(
+            attribute.span,
+            PropertyKind::Init,
+            match &attribute.key {
+                ImportAttributeKey::Identifier(ident) => {
+                    ast.property_key_static_identifier(ident.span, ident.name)
+                }
+                ImportAttributeKey::StringLiteral(lit) => {
+                    PropertyKey::StringLiteral(ast.alloc(lit.clone_in(ast.allocator)))
+                }
+            },
+            Expression::StringLiteral(ast.alloc(attribute.value.clone_in(ast.allocator))),
+            false,
+            false,
+            false,
+        )
+    });
+
+    // `assert` is deprecated in favor of `with`, which is the only key of the options
+    Some(ast.expression_object(
+        Span::default(),
+        ast.vec1(ast.object_property_kind_object_property(
+            with_clause.span,
+            PropertyKind::Init,
+            ast.property_key_static_identifier(Span::default(), "with"),
+            ast.expression_object(Span::default(), ast.vec_from_iter(attributes)),
+            false,
+            false,
+            false,
+        )),
+    ))
+}
+
+/// Makes `namespace.foo`, or `namespace["foo-bar"]` if the name of the export is not an identifier.
+pub(super) fn make_export_member<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    namespace: Expression<'a>,
+    name_span: Span,
+    name: Atom<'a>,
+) -> Expression<'a> {
+    if is_identifier_name(&name) {
+        ast.member_expression_static(span, namespace, ast.identifier_name(name_span, name), false)
+            .into()
+    } else {
+        ast.member_expression_computed(
+            span,
+            namespace,
+            ast.expression_string_literal(name_span, name, None),
+            false,
+        )
+        .into()
+    }
+}
+
+fn make_const_declarator<'a>(
+    ast: AstBuilder<'a>,
+    name: &'a str,
+    init: Expression<'a>,
+) -> VariableDeclarator<'a> {
+    ast.variable_declarator(
+        Span::default(),
+        VariableDeclarationKind::Const,
+        ast.binding_pattern(
+            ast.binding_pattern_kind_binding_identifier(Span::default(), name),
+            Option::<Box<'a, _>>::None,
+            false,
+        ),
+        Some(init),
+        false,
+    )
+}
+
+/// Makes `const foo = ..., bar = ...;`, or returns `None` if there are no declarators.
+fn make_const_declaration<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    declarators: impl IntoIterator<Item = VariableDeclarator<'a>>,
+) -> Option<Statement<'a>> {
+    let declarators = ast.vec_from_iter(declarators);
+    if declarators.is_empty() {
+        return None;
+    }
+
+    Some(Statement::VariableDeclaration(
+        ast.alloc_variable_declaration(span, VariableDeclarationKind::Const, declarators, false),
+    ))
+}
+
+/// Makes `export { __oxjest_import_{}__ as foo, ... };`.
+fn make_local_export<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    specifiers: impl IntoIterator<Item = (&'a str, ModuleExportName<'a>)>,
+) -> Statement<'a> {
+    Statement::ExportNamedDeclaration(ast.alloc_export_named_declaration(
+        span,
+        None,
+        ast.vec_from_iter(specifiers.into_iter().map(|(local, exported)| {
+            ast.export_specifier(
+                Span::default(),
+                ModuleExportName::IdentifierReference(
+                    ast.identifier_reference(Span::default(), local),
+                ),
+                exported,
+                ImportOrExportKind::Value,
+            )
+        })),
+        None,
+        ImportOrExportKind::Value,
+        Option::<Box<'a, _>>::None,
+    ))
+}
+
+/// Collects the names exported by the module explicitly, which take precedence over the ones of `export * from`.
+pub(super) fn collect_export_names(body: &[Statement]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in body {
+        match stmt {
+            Statement::ExportNamedDeclaration(decl) => {
+                names.extend(
+                    decl.specifiers
+                        .iter()
+                        .map(|specifier| specifier.exported.name().to_string()),
+                );
+                match &decl.declaration {
+                    Some(Declaration::VariableDeclaration(var_decl)) => names.extend(
+                        var_decl
+                            .declarations
+                            .iter()
+                            .flat_map(|declarator| declarator.id.get_binding_identifiers())
+                            .map(|ident| ident.name.to_string()),
+                    ),
+                    Some(declaration) => {
+                        names.extend(declaration.id().map(|ident| ident.name.to_string()));
+                    }
+                    None => {}
+                }
+            }
+            Statement::ExportDefaultDeclaration(_) => {
+                names.insert("default".to_string());
+            }
+            Statement::ExportAllDeclaration(decl) => {
+                names.extend(decl.exported.as_ref().map(|name| name.name().to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
+
+/// Identity of a dynamic import, which can be shared by the imports of the same module.
+#[derive(PartialEq)]
+struct ImportKey<'a> {
+    source: Atom<'a>,
+    attributes: Vec<(Atom<'a>, Atom<'a>)>,
+    phase: Option<ImportPhase>,
+}
+
+impl<'a> ImportKey<'a> {
+    fn new(
+        source: &StringLiteral<'a>,
+        with_clause: Option<&WithClause<'a>>,
+        phase: Option<ImportPhase>,
+    ) -> Self {
+        let mut attributes = with_clause
+            .iter()
+            .flat_map(|with_clause| with_clause.with_entries.iter())
+            .map(|attribute| {
+                let key = match &attribute.key {
+                    ImportAttributeKey::Identifier(ident) => ident.name,
+                    ImportAttributeKey::StringLiteral(lit) => lit.value,
+                };
+
+                (key, attribute.value.value)
+            })
+            .collect::<Vec<_>>();
+        // Keys of the attributes are unique, whose order doesn't matter
+        attributes.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+        Self {
+            source: source.value,
+            attributes,
+            phase,
+        }
+    }
+}
+
+/// Dynamic imports converted from the static imports and re-exports, shared by the ones of the same module.
+pub(super) struct DynamicImports<'a> {
+    ast: AstBuilder<'a>,
+    /// Names of the namespace objects of the imported modules.
+    namespaces: Vec<(ImportKey<'a>, &'a str)>,
+    /// Number of the local variables declared for re-exports.
+    export_count: usize,
+}
+
+impl<'a> DynamicImports<'a> {
+    pub(super) fn new(ast: AstBuilder<'a>) -> Self {
+        Self {
+            ast,
+            namespaces: Vec::new(),
+            export_count: 0,
+        }
+    }
+
+    /// Returns the name of the namespace object of the module, with `__oxjest_import_{}__ = await import("...")` if
+    /// the module is imported for the first time.
+    pub(super) fn namespace(
+        &mut self,
+        source: &StringLiteral<'a>,
+        with_clause: Option<&WithClause<'a>>,
+        phase: Option<ImportPhase>,
+    ) -> (&'a str, Option<VariableDeclarator<'a>>) {
+        let key = ImportKey::new(source, with_clause, phase);
+        if let Some((_, import_name)) = self.namespaces.iter().find(|(other, _)| *other == key) {
+            return (import_name, None);
+        }
+
+        let ast = self.ast;
+        let import_name = ast.str(&format!("__oxjest_import_{}__", self.namespaces.len()));
+        self.namespaces.push((key, import_name));
+
+        let await_import = make_const_declarator(
+            ast,
+            import_name,
+            ast.expression_await(
+                Span::default(),
+                ast.expression_import(
+                    Span::default(),
+                    ast.expression_string_literal(source.span, source.value, source.raw),
+                    make_import_options(ast, with_clause),
+                    phase,
+                ),
+            ),
+        );
+
+        (import_name, Some(await_import))
+    }
+
+    /// Turns `import foo, { bar } from "..."` into
+    /// `const __oxjest_import_{}__ = await import("..."), foo = __oxjest_import_{}__.default, ...;`.
+    /// Live bindings are accessed through the namespace object instead of being declared.
+    pub(super) fn convert_import(
+        &mut self,
+        decl: &ImportDeclaration<'a>,
+        import_name: &'a str,
+        await_import: Option<VariableDeclarator<'a>>,
+        live_bindings: &LiveBindings<'_, 'a>,
+    ) -> Option<Statement<'a>> {
+        let ast = self.ast;
+
+        let specifiers = decl
+            .specifiers
+            .iter()
+            .flatten()
+            .filter(|specifier| !live_bindings.contains(specifier));
+        let declarations = specifiers.map(|specifier| {
+            let namespace = ast.expression_identifier(Span::default(), import_name);
+            let init = match specifier {
+                ImportDeclarationSpecifier::ImportNamespaceSpecifier(_) => namespace,
+                ImportDeclarationSpecifier::ImportDefaultSpecifier(specifier) => ast
+                    .member_expression_static(
+                        Span::default(),
+                        namespace,
+                        ast.identifier_name(specifier.span, "default"),
+                        false,
+                    )
+                    .into(),
+                ImportDeclarationSpecifier::ImportSpecifier(specifier) => make_export_member(
+                    ast,
+                    Span::default(),
+                    namespace,
+                    specifier.imported.span(),
+                    specifier.imported.name(),
+                ),
+            };
+
+            ast.variable_declarator(
+                Span::default(),
+                VariableDeclarationKind::Const,
+                ast.binding_pattern(
+                    BindingPatternKind::BindingIdentifier(
+                        ast.alloc(specifier.local().clone_in(ast.allocator)),
+                    ),
+                    Option::<Box<'a, _>>::None,
+                    false,
+                ),
+                Some(init),
+                false,
+            )
+        });
+
+        make_const_declaration(ast, decl.span, await_import.into_iter().chain(declarations))
+    }
+
+    /// Turns `export { foo, bar as baz } from "..."` into
+    /// `const __oxjest_import_{}__ = await import("..."), __oxjest_export_{}__ = __oxjest_import_{}__.foo, ...;`
+    /// and `export { __oxjest_export_{}__ as foo, ... };`.
+    pub(super) fn convert_reexport(
+        &mut self,
+        decl: &ExportNamedDeclaration<'a>,
+        source: &StringLiteral<'a>,
+    ) -> Vec<Statement<'a>> {
+        let ast = self.ast;
+        let specifiers = decl.specifiers.iter().map(|specifier| {
+            (
+                specifier.local.span(),
+                specifier.local.name(),
+                specifier.exported.clone_in(ast.allocator),
+            )
+        });
+
+        self.reexport(decl.span, source, decl.with_clause.as_deref(), specifiers)
+    }
+
+    /// Turns `export * from "..."` into the re-exports of the names, which are listed from the module graph.
+    pub(super) fn convert_star_reexport(
+        &mut self,
+        decl: &ExportAllDeclaration<'a>,
+        names: &[String],
+    ) -> Vec<Statement<'a>> {
+        let ast = self.ast;
+        let specifiers = names.iter().map(|name| {
+            let name = ast.atom(name);
+            let exported = if is_identifier_name(&name) {
+                ModuleExportName::IdentifierName(ast.identifier_name(Span::default(), name))
+            } else {
+                ModuleExportName::StringLiteral(ast.string_literal(Span::default(), name, None))
+            };
+
+            (Span::default(), name, exported)
+        });
+
+        self.reexport(
+            decl.span,
+            &decl.source,
+            decl.with_clause.as_deref(),
+            specifiers,
+        )
+    }
+
+    fn reexport(
+        &mut self,
+        span: Span,
+        source: &StringLiteral<'a>,
+        with_clause: Option<&WithClause<'a>>,
+        specifiers: impl IntoIterator<Item = (Span, Atom<'a>, ModuleExportName<'a>)>,
+    ) -> Vec<Statement<'a>> {
+        let ast = self.ast;
+        let (import_name, await_import) = self.namespace(source, with_clause, None);
+
+        let mut declarations = Vec::new();
+        let mut exports = Vec::new();
+        for (name_span, name, exported) in specifiers {
+            let local_name = ast.str(&format!("__oxjest_export_{}__", self.export_count));
+            self.export_count += 1;
+
+            let namespace = ast.expression_identifier(Span::default(), import_name);
+            let member = make_export_member(ast, Span::default(), namespace, name_span, name);
+            declarations.push(make_const_declarator(ast, local_name, member));
+            exports.push((local_name, exported));
+        }
+
+        let declaration = make_const_declaration(
+            ast,
+            Span::default(),
+            await_import.into_iter().chain(declarations),
+        );
+        let local_export = make_local_export(ast, span, exports);
+
+        declaration.into_iter().chain([local_export]).collect()
+    }
+
+    /// Turns `export * as foo from "..."` into `const __oxjest_import_{}__ = await import("...");`
+    /// and `export { __oxjest_import_{}__ as foo };`.
+    pub(super) fn convert_namespace_reexport(
+        &mut self,
+        decl: &ExportAllDeclaration<'a>,
+        exported: &ModuleExportName<'a>,
+    ) -> Vec<Statement<'a>> {
+        let ast = self.ast;
+        let (import_name, await_import) =
+            self.namespace(&decl.source, decl.with_clause.as_deref(), None);
+
+        make_const_declaration(ast, Span::default(), await_import)
+            .into_iter()
+            .chain([make_local_export(
+                ast,
+                decl.span,
+                [(import_name, exported.clone_in(ast.allocator))],
+            )])
+            .collect()
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use oxc::allocator::{TakeIn, Vec as ArenaVec};
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{
+    ArrayExpressionElement, ArrowFunctionExpression, Expression, Function, IdentifierReference,
+    ObjectPropertyKind, Statement, UnaryOperator,
+};
+use oxc::ast_visit::{Visit, walk};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::semantic::{ScopeFlags, ScopeId, Scoping, SymbolId};
+use oxc::span::{Atom, Span};
+
+use crate::jest::is_jest_fn_call;
+
+/// Checks that the variable can be referenced from hoisted mock factories, as babel-plugin-jest-hoist allows.
+fn is_allowed_in_factory(name: &str) -> bool {
+    const JEST_OBJECT_NAME: &str = "jest";
+    const MOCK_PREFIX: &str = "mock";
+
+    name == JEST_OBJECT_NAME
+        || name
+            .get(..MOCK_PREFIX.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(MOCK_PREFIX))
+}
+
+/// References in a mock factory to the variables declared outside of the factory.
+pub(super) struct FactoryReferences<'s, 'a> {
+    scoping: &'s Scoping,
+    factory_scope_id: Option<ScopeId>,
+    /// Depth of the functions, whose bodies are not evaluated until they are called.
+    depth: usize,
+    /// The depth of the factory body, which is evaluated as soon as the factory is called.
+    eager_depth: usize,
+    /// References to the variables not allowed to be referenced.
+    pub(super) illegal: Vec<(Atom<'a>, Span)>,
+    /// Allowed variables referenced as soon as the factory is called.
+    pub(super) eager: Vec<SymbolId>,
+}
+
+impl<'s, 'a> FactoryReferences<'s, 'a> {
+    pub(super) fn collect(scoping: &'s Scoping, factory: &Expression<'a>) -> Self {
+        let factory_scope_id = match factory {
+            Expression::ArrowFunctionExpression(func) => func.scope_id.get(),
+            Expression::FunctionExpression(func) => func.scope_id.get(),
+            _ => None,
+        };
+
+        let mut references = Self {
+            scoping,
+            factory_scope_id,
+            depth: 0,
+            eager_depth: usize::from(factory_scope_id.is_some()),
+            illegal: Vec::new(),
+            eager: Vec::new(),
+        };
+
+        references.visit_expression(factory);
+        references
+    }
+}
+
+impl<'a> Visit<'a> for FactoryReferences<'_, 'a> {
+    fn visit_identifier_reference(&mut self, ident: &IdentifierReference<'a>) {
+        // Generated identifiers don't refer to any variables
+        let Some(reference_id) = ident.reference_id.get() else {
+            return;
+        };
+
+        // Globals are always available
+        let Some(symbol_id) = self.scoping.get_reference(reference_id).symbol_id() else {
+            return;
+        };
+
+        let scope_id = self.scoping.symbol_scope_id(symbol_id);
+        if let Some(factory_scope_id) = self.factory_scope_id
+            && self
+                .scoping
+                .scope_ancestors(scope_id)
+                .any(|scope_id| scope_id == factory_scope_id)
+        {
+            return;
+        }
+
+        if !is_allowed_in_factory(&ident.name) {
+            self.illegal.push((ident.name, ident.span));
+        } else if self.depth <= self.eager_depth {
+            self.eager.push(symbol_id);
+        }
+    }
+
+    fn visit_function(&mut self, it: &Function<'a>, flags: ScopeFlags) {
+        self.depth += 1;
+        walk::walk_function(self, it, flags);
+        self.depth -= 1;
+    }
+
+    fn visit_arrow_function_expression(&mut self, it: &ArrowFunctionExpression<'a>) {
+        self.depth += 1;
+        walk::walk_arrow_function_expression(self, it);
+        self.depth -= 1;
+    }
+}
+
+/// Checks that evaluating the expression has no side effects, so that it can be evaluated earlier.
+/// Variables referenced by the expression are collected as the dependencies.
+fn is_side_effect_free(
+    expr: &Expression,
+    scoping: &Scoping,
+    dependencies: &mut Vec<SymbolId>,
+) -> bool {
+    match expr {
+        Expression::BooleanLiteral(_)
+        | Expression::NullLiteral(_)
+        | Expression::NumericLiteral(_)
+        | Expression::BigIntLiteral(_)
+        | Expression::RegExpLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::ArrowFunctionExpression(_)
+        | Expression::FunctionExpression(_) => true,
+        Expression::TemplateLiteral(lit) => lit
+            .expressions
+            .iter()
+            .all(|expr| is_side_effect_free(expr, scoping, dependencies)),
+        Expression::Identifier(ident) => {
+            if let Some(reference_id) = ident.reference_id.get()
+                && let Some(symbol_id) = scoping.get_reference(reference_id).symbol_id()
+            {
+                dependencies.push(symbol_id);
+            }
+
+            true
+        }
+        Expression::ParenthesizedExpression(expr) => {
+            is_side_effect_free(&expr.expression, scoping, dependencies)
+        }
+        Expression::UnaryExpression(expr) => {
+            expr.operator != UnaryOperator::Delete
+                && is_side_effect_free(&expr.argument, scoping, dependencies)
+        }
+        Expression::ArrayExpression(array) => array.elements.iter().all(|element| match element {
+            ArrayExpressionElement::SpreadElement(spread) => {
+                is_side_effect_free(&spread.argument, scoping, dependencies)
+            }
+            ArrayExpressionElement::Elision(_) => true,
+            _ => is_side_effect_free(element.to_expression(), scoping, dependencies),
+        }),
+        Expression::ObjectExpression(object) => {
+            object.properties.iter().all(|property| match property {
+                ObjectPropertyKind::ObjectProperty(property) => {
+                    property
+                        .key
+                        .as_expression()
+                        .is_none_or(|key| is_side_effect_free(key, scoping, dependencies))
+                        && is_side_effect_free(&property.value, scoping, dependencies)
+                }
+                ObjectPropertyKind::SpreadProperty(spread) => {
+                    is_side_effect_free(&spread.argument, scoping, dependencies)
+                }
+            })
+        }
+        Expression::CallExpression(call) => {
+            // jest.fn(...) and jest.fn(...).mockReturnValue(...), etc.
+            let is_mock_fn = is_jest_fn_call(call, scoping)
+                || matches!(
+                    &call.callee,
+                    Expression::StaticMemberExpression(callee)
+                        if callee.property.name.starts_with("mock")
+                            && matches!(&callee.object, Expression::CallExpression(_))
+                            && is_side_effect_free(&callee.object, scoping, dependencies)
+                );
+
+            is_mock_fn
+                && call.arguments.iter().all(|argument| {
+                    argument
+                        .as_expression()
+                        .is_some_and(|expr| is_side_effect_free(expr, scoping, dependencies))
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Top-level variable declarators, indexed by their symbols.
+struct Variables<'b, 'a> {
+    body: &'b ArenaVec<'a, Statement<'a>>,
+    scoping: &'b Scoping,
+    declarators: HashMap<SymbolId, (usize, usize)>,
+    hoistable: HashMap<SymbolId, bool>,
+    /// Variables marked as hoistable while checking, in the order they are checked.
+    checked: Vec<SymbolId>,
+}
+
+impl<'b, 'a> Variables<'b, 'a> {
+    fn new(body: &'b ArenaVec<'a, Statement<'a>>, scoping: &'b Scoping) -> Self {
+        let mut declarators = HashMap::new();
+        for (i, stmt) in body.iter().enumerate() {
+            let Statement::VariableDeclaration(decl) = stmt else {
+                continue;
+            };
+
+            for (j, declarator) in decl.declarations.iter().enumerate() {
+                if let Some(symbol_id) = declarator
+                    .id
+                    .get_binding_identifier()
+                    .and_then(|ident| ident.symbol_id.get())
+                {
+                    declarators.insert(symbol_id, (i, j));
+                }
+            }
+        }
+
+        Self {
+            body,
+            scoping,
+            declarators,
+            hoistable: HashMap::new(),
+            checked: Vec::new(),
+        }
+    }
+
+    /// Checks that the variable can be hoisted with its dependencies, without changing the behaviour.
+    fn is_hoistable(&mut self, symbol_id: SymbolId) -> bool {
+        if let Some(&hoistable) = self.hoistable.get(&symbol_id) {
+            return hoistable;
+        }
+
+        // Function declarations are already hoisted
+        if self.scoping.symbol_flags(symbol_id).is_function() {
+            return true;
+        }
+
+        let Some(&(i, j)) = self.declarators.get(&symbol_id) else {
+            self.hoistable.insert(symbol_id, false);
+            return false;
+        };
+
+        let Statement::VariableDeclaration(decl) = &self.body[i] else {
+            unreachable!();
+        };
+
+        // Mark as hoistable while checking, for circular references
+        let start = self.checked.len();
+        self.checked.push(symbol_id);
+        self.hoistable.insert(symbol_id, true);
+
+        let mut dependencies = Vec::new();
+        let hoistable = decl.declarations[j]
+            .init
+            .as_ref()
+            .is_none_or(|init| is_side_effect_free(init, self.scoping, &mut dependencies))
+            && dependencies
+                .into_iter()
+                .all(|dependency| self.is_hoistable(dependency));
+
+        // Variables checked since may be hoistable only by referencing this variable
+        if !hoistable {
+            for checked in self.checked.drain(start..) {
+                if self.hoistable.get(&checked) == Some(&true) {
+                    self.hoistable.remove(&checked);
+                }
+            }
+        }
+
+        self.hoistable.insert(symbol_id, hoistable);
+        hoistable
+    }
+}
+
+/// Takes the variables referenced by hoisted mock factories out of the module body, with their dependencies.
+/// Variables that can't be hoisted are reported as warnings, as they may not be initialized when the factory is called.
+pub(super) fn take_variables<'a>(
+    body: &mut ArenaVec<'a, Statement<'a>>,
+    symbol_ids: &[SymbolId],
+    scoping: &Scoping,
+    ast: AstBuilder<'a>,
+    diagnostics: &mut Vec<OxcDiagnostic>,
+) -> Vec<Statement<'a>> {
+    let mut variables = Variables::new(body, scoping);
+    for &symbol_id in symbol_ids {
+        if variables.is_hoistable(symbol_id) {
+            continue;
+        }
+
+        let name = scoping.symbol_name(symbol_id);
+        diagnostics.push(
+            OxcDiagnostic::warn(format!(
+                "`{name}` is referenced by a hoisted mock factory, but can't be hoisted as it may have side effects."
+            ))
+            .with_label(scoping.symbol_span(symbol_id).label("Declared here"))
+            .with_help(format!(
+                "Reference `{name}` lazily in the factory, such as `(...args) => {name}(...args)`."
+            )),
+        );
+    }
+
+    let Variables {
+        declarators,
+        hoistable,
+        ..
+    } = variables;
+
+    let mut hoisted = Vec::new();
+    for (i, stmt) in body.iter_mut().enumerate() {
+        let Statement::VariableDeclaration(decl) = stmt else {
+            continue;
+        };
+
+        let is_hoisted = |j: usize, symbol_id: Option<SymbolId>| {
+            symbol_id.is_some_and(|symbol_id| {
+                hoistable.get(&symbol_id) == Some(&true)
+                    && declarators.get(&symbol_id) == Some(&(i, j))
+            })
+        };
+
+        if !decl.declarations.iter().enumerate().any(|(j, declarator)| {
+            is_hoisted(
+                j,
+                declarator
+                    .id
+                    .get_binding_identifier()
+                    .and_then(|ident| ident.symbol_id.get()),
+            )
+        }) {
+            continue;
+        }
+
+        let mut declarations = ast.vec();
+        let mut hoisted_declarations = ast.vec();
+        for (j, declarator) in decl
+            .declarations
+            .take_in(ast.allocator)
+            .into_iter()
+            .enumerate()
+        {
+            let symbol_id = declarator
+                .id
+                .get_binding_identifier()
+                .and_then(|ident| ident.symbol_id.get());
+
+            if is_hoisted(j, symbol_id) {
+                hoisted_declarations.push(declarator);
+            } else {
+                declarations.push(declarator);
+            }
+        }
+
+        decl.declarations = declarations;
+        hoisted.push(Statement::VariableDeclaration(
+            ast.alloc_variable_declaration(decl.span, decl.kind, hoisted_declarations, false),
+        ));
+    }
+
+    body.retain(
+        |stmt| !matches!(stmt, Statement::VariableDeclaration(decl) if decl.declarations.is_empty()),
+    );
+
+    hoisted
+}
@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{
+    CallExpression, Expression, ImportDeclarationSpecifier, ModuleExportName, NumberBase,
+    Statement, TaggedTemplateExpression,
+};
+use oxc::ast_visit::{VisitMut, walk_mut};
+use oxc::semantic::{Scoping, SymbolId};
+use oxc::span::{Atom, GetSpan, Span};
+
+use crate::pass::convert_mocks::dynamic_imports::make_export_member;
+
+/// Collects the local variables exported by `export { ... }`, which need to be declared in the module.
+pub(super) fn collect_local_exports(body: &[Statement], scoping: &Scoping) -> HashSet<SymbolId> {
+    body.iter()
+        .filter_map(|stmt| match stmt {
+            Statement::ExportNamedDeclaration(decl) if decl.source.is_none() => Some(decl),
+            _ => None,
+        })
+        .flat_map(|decl| decl.specifiers.iter())
+        .filter_map(|specifier| match &specifier.local {
+            ModuleExportName::IdentifierReference(ident) => ident.reference_id.get(),
+            _ => None,
+        })
+        .filter_map(|reference_id| scoping.get_reference(reference_id).symbol_id())
+        .collect()
+}
+
+/// Imported bindings, whose references are rewritten into member accesses on the namespace objects of the dynamic
+/// imports, so that they keep reflecting the current values of the exports.
+pub(super) struct LiveBindings<'s, 'a> {
+    ast: AstBuilder<'a>,
+    scoping: &'s Scoping,
+    bindings: HashMap<SymbolId, (Atom<'a>, Atom<'a>)>,
+}
+
+impl<'s, 'a> LiveBindings<'s, 'a> {
+    pub(super) fn new(ast: AstBuilder<'a>, scoping: &'s Scoping) -> Self {
+        Self {
+            ast,
+            scoping,
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Registers the binding of the specifier as a member of the namespace object.
+    /// Namespace specifiers are not registered, as the namespace object itself is live.
+    pub(super) fn insert(
+        &mut self,
+        specifier: &ImportDeclarationSpecifier<'a>,
+        import_name: Atom<'a>,
+    ) {
+        let property = match specifier {
+            ImportDeclarationSpecifier::ImportDefaultSpecifier(_) => Atom::from("default"),
+            ImportDeclarationSpecifier::ImportSpecifier(specifier) => specifier.imported.name(),
+            ImportDeclarationSpecifier::ImportNamespaceSpecifier(_) => return,
+        };
+
+        if let Some(symbol_id) = specifier.local().symbol_id.get() {
+            self.bindings.insert(symbol_id, (import_name, property));
+        }
+    }
+
+    pub(super) fn contains(&self, specifier: &ImportDeclarationSpecifier) -> bool {
+        specifier
+            .local()
+            .symbol_id
+            .get()
+            .is_some_and(|symbol_id| self.bindings.contains_key(&symbol_id))
+    }
+
+    /// Makes `__oxjest_import_{}__.foo` if the expression refers to an imported binding.
+    fn make_member(&self, expr: &Expression<'a>) -> Option<Expression<'a>> {
+        let Expression::Identifier(ident) = expr else {
+            return None;
+        };
+
+        let symbol_id = self
+            .scoping
+            .get_reference(ident.reference_id.get()?)
+            .symbol_id()?;
+        let (import_name, property) = self.bindings.get(&symbol_id)?;
+
+        Some(make_export_member(
+            self.ast,
+            ident.span,
+            self.ast
+                .expression_identifier(Span::default(), *import_name),
+            Span::default(),
+            *property,
+        ))
+    }
+
+    /// Makes `(0, __oxjest_import_{}__.foo)` for the callee, not to pass the namespace object as `this`.
+    fn make_callee(&self, expr: &Expression<'a>) -> Option<Expression<'a>> {
+        let member = self.make_member(expr)?;
+
+        Some(self.ast.expression_sequence(
+            expr.span(),
+            self.ast.vec_from_array([
+                self.ast.expression_numeric_literal(
+                    Span::default(),
+                    0.0,
+                    None,
+                    NumberBase::Decimal,
+                ),
+                member,
+            ]),
+        ))
+    }
+}
+
+impl<'a> VisitMut<'a> for LiveBindings<'_, 'a> {
+    fn visit_expression(&mut self, it: &mut Expression<'a>) {
+        if let Some(member) = self.make_member(it) {
+            *it = member;
+            return;
+        }
+
+        walk_mut::walk_expression(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &mut CallExpression<'a>) {
+        if let Some(callee) = self.make_callee(&it.callee) {
+            it.callee = callee;
+        }
+
+        walk_mut::walk_call_expression(self, it);
+    }
+
+    fn visit_tagged_template_expression(&mut self, it: &mut TaggedTemplateExpression<'a>) {
+        if let Some(tag) = self.make_callee(&it.tag) {
+            it.tag = tag;
+        }
+
+        walk_mut::walk_tagged_template_expression(self, it);
+    }
+}
@@ -0,0 +1,620 @@
+use oxc::allocator::{Box, CloneIn, TakeIn, Vec as ArenaVec};
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{
+    Argument, BindingPatternKind, CallExpression, Expression, ImportDeclarationSpecifier,
+    ImportOrExportKind, ObjectPattern, Program, PropertyKey, Statement, StringLiteral,
+    VariableDeclarationKind,
+};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::semantic::Scoping;
+use oxc::span::{GetSpan, Span};
+use oxc_traverse::{Ancestor, Traverse, TraverseCtx};
+
+use crate::jest::{
+    is_jest_mock_call, is_jest_mock_chain, is_jest_unmock_call, is_jest_unstable_mock_module_call,
+};
+use crate::module_graph::ModuleGraph;
+use crate::pass::async_functions::{AsyncFunctions, AwaitScope};
+use crate::pass::runtime::{make_import_meta_url, make_runtime_call};
+
+/// Name of the `require` function created by `createRequire`, for the calls that can't be awaited.
+const OXJEST_REQUIRE_NAME: &str = "__oxjest_require__";
+
+/// Checks that the call expression is `require("...")`, referencing the global `require`.
+pub(crate) fn is_require_call(call: &CallExpression, scoping: &Scoping) -> bool {
+    const REQUIRE: &str = "require";
+
+    let Expression::Identifier(ident) = &call.callee else {
+        return false;
+    };
+
+    ident.name == REQUIRE
+        && ident
+            .reference_id
+            .get()
+            .is_none_or(|reference_id| scoping.get_reference(reference_id).symbol_id().is_none())
+        && matches!(call.arguments.as_slice(), [Argument::StringLiteral(_)])
+}
+
+/// Returns the module specifier if the expression is `require("...")`.
+fn as_require_call<'a, 'e>(
+    expr: Option<&'e Expression<'a>>,
+    scoping: &Scoping,
+) -> Option<&'e CallExpression<'a>> {
+    match expr? {
+        Expression::CallExpression(call) if is_require_call(call, scoping) => Some(call),
+        _ => None,
+    }
+}
+
+/// Returns the module specifier of `require("...")`.
+fn require_source<'a, 'e>(call: &'e CallExpression<'a>) -> &'e StringLiteral<'a> {
+    let Some(Argument::StringLiteral(lit)) = call.arguments.first() else {
+        // SAFETY: Already checked by `is_require_call`
+        unreachable!();
+    };
+
+    lit
+}
+
+/// Makes `import { foo, bar as baz } from "..."` from `{ foo, bar: baz }`, or returns `None` if the pattern has
+/// computed keys, default values, nested patterns or rest elements.
+fn make_import_specifiers<'a>(
+    ast: AstBuilder<'a>,
+    pattern: &ObjectPattern<'a>,
+) -> Option<ArenaVec<'a, ImportDeclarationSpecifier<'a>>> {
+    if pattern.rest.is_some() {
+        return None;
+    }
+
+    let specifiers = pattern.properties.iter().map(|property| {
+        let imported = match &property.key {
+            PropertyKey::StaticIdentifier(ident) => {
+                ast.module_export_name_identifier_name(ident.span, ident.name)
+            }
+            PropertyKey::StringLiteral(lit) => {
+                ast.module_export_name_string_literal(lit.span, lit.value, lit.raw)
+            }
+            _ => return None,
+        };
+        let BindingPatternKind::BindingIdentifier(local) = &property.value.kind else {
+            return None;
+        };
+
+        Some(ast.import_declaration_specifier_import_specifier(
+            property.span,
+            imported,
+            (**local).clone_in_with_semantic_ids(ast.allocator),
+            ImportOrExportKind::Value,
+        ))
+    });
+
+    specifiers
+        .collect::<Option<Vec<_>>>()
+        .map(|specifiers| ast.vec_from_iter(specifiers))
+}
+
+/// Checks that the statement is evaluated before the others, such as imports and the mocks to be hoisted.
+fn is_hoisted_statement(stmt: &Statement, scoping: &Scoping) -> bool {
+    match stmt {
+        Statement::ImportDeclaration(_) | Statement::ExportAllDeclaration(_) => true,
+        Statement::ExportNamedDeclaration(decl) => decl.source.is_some(),
+        Statement::ExpressionStatement(stmt) => {
+            is_jest_mock_chain(&stmt.expression, scoping)
+                || matches!(
+                    &stmt.expression,
+                    Expression::CallExpression(call)
+                        if is_jest_mock_call(call, scoping)
+                            || is_jest_unmock_call(call, scoping)
+                            || is_jest_unstable_mock_module_call(call, scoping)
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Makes the value `require` returns from the namespace object of the module:
+/// the namespace itself for ES modules, `module.exports` as the default export for CommonJS modules,
+/// and the one determined by `__oxjest__.interopRequire(namespace)` if not known statically.
+fn make_required_value<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    namespace: Expression<'a>,
+    is_es_module: Option<bool>,
+) -> Expression<'a> {
+    match is_es_module {
+        Some(true) => namespace,
+        Some(false) => ast
+            .member_expression_static(
+                span,
+                namespace,
+                ast.identifier_name(Span::default(), "default"),
+                false,
+            )
+            .into(),
+        None => make_runtime_call(ast, span, "interopRequire", [namespace.into()]),
+    }
+}
+
+fn make_import_declaration<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    specifiers: Option<ArenaVec<'a, ImportDeclarationSpecifier<'a>>>,
+    source: &StringLiteral<'a>,
+) -> Statement<'a> {
+    Statement::ImportDeclaration(ast.alloc_import_declaration::<Option<Box<'a, _>>>(
+        span,
+        specifiers,
+        source.clone_in(ast.allocator),
+        None,
+        None,
+        ImportOrExportKind::Value,
+    ))
+}
+
+/// Turns `require("...")` calls into imports, as `require` is not available in ESM:
+///
+/// - Top-level `const foo = require("...")` and `require("...")` statements become static imports, which are turned
+///   into dynamic imports by [`ConvertMocks`](super::convert_mocks::ConvertMocks) if the module has hoisted mocks.
+/// - The others in async functions, or the callbacks of tests and hooks, become `await import("...")`, making the
+///   callbacks async.
+/// - The ones that can't be awaited fall back to `require` created by `createRequire(import.meta.url)`.
+pub(crate) struct ConvertRequire<'a> {
+    async_functions: AsyncFunctions,
+    /// Whether `require` needs to be created for the calls that can't be awaited.
+    create_require: bool,
+    /// Number of the namespace objects imported for the modules not known to be ES modules or CommonJS modules.
+    namespace_count: usize,
+    /// Graph of the imported modules, to check whether the required modules are ES modules.
+    module_graph: Option<&'a ModuleGraph<'a>>,
+    pub(crate) diagnostics: Vec<OxcDiagnostic>,
+}
+
+impl<'a> ConvertRequire<'a> {
+    pub(crate) fn new() -> Self {
+        Self {
+            async_functions: AsyncFunctions::new(),
+            create_require: false,
+            namespace_count: 0,
+            module_graph: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_module_graph(mut self, module_graph: Option<&'a ModuleGraph<'a>>) -> Self {
+        self.module_graph = module_graph;
+        self
+    }
+
+    /// Checks that the module is an ES module, or returns `None` if not known statically.
+    fn is_es_module(&self, specifier: &str) -> Option<bool> {
+        self.module_graph?.is_es_module(specifier)
+    }
+
+    /// Turns top-level `const foo = require("...")`, `const { foo } = require("...")` and `require("...")`
+    /// statements into static imports, or returns `None` if the statement is not one of them.
+    fn make_imports(
+        &mut self,
+        ast: AstBuilder<'a>,
+        stmt: &mut Statement<'a>,
+        scoping: &Scoping,
+    ) -> Option<Vec<Statement<'a>>> {
+        let span = stmt.span();
+        let decl = match stmt {
+            Statement::ExpressionStatement(stmt) => {
+                // import "<id>";
+                let call = as_require_call(Some(&stmt.expression), scoping)?;
+                return Some(vec![make_import_declaration(
+                    ast,
+                    span,
+                    None,
+                    require_source(call),
+                )]);
+            }
+            // Imported bindings can't be reassigned, as well as `const` ones
+            Statement::VariableDeclaration(decl)
+                if decl.kind == VariableDeclarationKind::Const && decl.declarations.len() == 1 =>
+            {
+                decl
+            }
+            _ => return None,
+        };
+
+        let declarator = &mut decl.declarations[0];
+        let call = as_require_call(declarator.init.as_ref(), scoping)?;
+        let source = require_source(call);
+        let specifier = match &declarator.id.kind {
+            // import { foo, bar as baz } from "<id>";
+            BindingPatternKind::ObjectPattern(pattern) => {
+                let specifiers = make_import_specifiers(ast, pattern)?;
+                return Some(vec![make_import_declaration(
+                    ast,
+                    span,
+                    Some(specifiers),
+                    source,
+                )]);
+            }
+            BindingPatternKind::BindingIdentifier(ident) => ident,
+            _ => return None,
+        };
+
+        let local = (**specifier).clone_in_with_semantic_ids(ast.allocator);
+        let specifiers = match self.is_es_module(&source.value) {
+            // import * as foo from "<id>";
+            Some(true) => {
+                ast.import_declaration_specifier_import_namespace_specifier(specifier.span, local)
+            }
+            // import foo from "<id>";
+            Some(false) => {
+                ast.import_declaration_specifier_import_default_specifier(specifier.span, local)
+            }
+            None => {
+                // import * as __oxjest_required_{}__ from "<id>";
+                // const foo = __oxjest__.interopRequire(__oxjest_required_{}__);
+                let namespace = ast.str(&format!("__oxjest_required_{}__", self.namespace_count));
+                self.namespace_count += 1;
+
+                let import = make_import_declaration(
+                    ast,
+                    Span::default(),
+                    Some(
+                        ast.vec1(ast.import_declaration_specifier_import_namespace_specifier(
+                            Span::default(),
+                            ast.binding_identifier(Span::default(), namespace),
+                        )),
+                    ),
+                    source,
+                );
+                declarator.init = Some(make_required_value(
+                    ast,
+                    call.span,
+                    ast.expression_identifier(Span::default(), namespace),
+                    None,
+                ));
+
+                return Some(vec![import, stmt.take_in(ast.allocator)]);
+            }
+        };
+
+        Some(vec![make_import_declaration(
+            ast,
+            span,
+            Some(ast.vec1(specifiers)),
+            source,
+        )])
+    }
+}
+
+impl<'a, State> Traverse<'a, State> for ConvertRequire<'a> {
+    fn enter_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        // Static imports are evaluated before the other statements, thus only the ones preceded by the imports and
+        // the hoisted mocks can be static imports, while the others become await imports
+        let body = node.body.take_in(ctx.ast.allocator);
+        let mut is_hoisted = true;
+        for mut stmt in body {
+            if is_hoisted {
+                if let Some(imports) = self.make_imports(ctx.ast, &mut stmt, ctx.scoping()) {
+                    node.body.extend(imports);
+                    continue;
+                }
+
+                is_hoisted = is_hoisted_statement(&stmt, ctx.scoping());
+            }
+
+            node.body.push(stmt);
+        }
+    }
+
+    fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        if !self.create_require {
+            return;
+        }
+
+        // const __oxjest_require__ = __oxjest__.createRequire(import.meta.url);
+        let decl = ctx.ast.declaration_variable(
+            Span::default(),
+            VariableDeclarationKind::Const,
+            ctx.ast.vec1(ctx.ast.variable_declarator(
+                Span::default(),
+                VariableDeclarationKind::Const,
+                ctx.ast.binding_pattern(
+                    ctx.ast.binding_pattern_kind_binding_identifier(
+                        Span::default(),
+                        OXJEST_REQUIRE_NAME,
+                    ),
+                    Option::<Box<'a, _>>::None,
+                    false,
+                ),
+                Some(make_runtime_call(
+                    ctx.ast,
+                    Span::default(),
+                    "createRequire",
+                    [make_import_meta_url(ctx.ast).into()],
+                )),
+                false,
+            )),
+            false,
+        );
+
+        node.body.insert(0, Statement::from(decl));
+    }
+
+    fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.async_functions.exit_expression(node);
+
+        let Expression::CallExpression(call) = node else {
+            return;
+        };
+
+        if !is_require_call(call, ctx.scoping()) {
+            return;
+        }
+
+        // `Some(None)` for the top level, where `await` is always allowed
+        let scope_id = AwaitScope::from_ctx(ctx)
+            .filter(AwaitScope::is_awaitable)
+            .map(|scope| match scope {
+                AwaitScope::Program => None,
+                AwaitScope::Function { scope_id, .. } => Some(scope_id),
+            });
+        let Expression::Identifier(ident) = &call.callee else {
+            // SAFETY: Already checked above
+            unreachable!();
+        };
+        ctx.delete_reference_for_identifier(ident);
+
+        let Some(scope_id) = scope_id else {
+            // require("<id>") -> __oxjest_require__("<id>")
+            self.diagnostics.push(
+                OxcDiagnostic::warn(
+                    "`require` can't be converted into an await import here, thus the module is required without the ESM mocks.",
+                )
+                .with_label(call.span.label("Required synchronously"))
+                .with_help("Call `require` in an async function, a test or a hook, or use `await import()` instead."),
+            );
+            self.create_require = true;
+            call.callee = ctx
+                .ast
+                .expression_identifier(call.callee.span(), OXJEST_REQUIRE_NAME);
+            return;
+        };
+
+        if let Some(scope_id) = scope_id {
+            self.async_functions.insert(scope_id);
+        }
+
+        // require("<id>") -> await import("<id>")
+        let is_es_module = self.is_es_module(&require_source(call).value);
+        let specifier = call.arguments.take_in(ctx.ast.allocator).remove(0);
+        let await_import = ctx.ast.expression_await(
+            call.span,
+            ctx.ast
+                .expression_import(call.span, specifier.into_expression(), None, None),
+        );
+
+        // The value is not needed for `require("<id>");`
+        *node = if matches!(ctx.parent(), Ancestor::ExpressionStatementExpression(_)) {
+            await_import
+        } else {
+            make_required_value(ctx.ast, call.span, await_import, is_es_module)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_path, format_errors, resolve, transform, transform_with};
+    use oxc::allocator::Allocator;
+
+    #[test]
+    fn test_convert_require_top_level() {
+        let source_text = r#"
+        require("./setup.js");
+        const greeter = require("./greeter.js");
+        const { count, "increment": inc } = require("./counter.js");
+        jest.mock("./logger.js");
+        const logger = require("./logger.js");
+
+        let formatter = require("./formatter.js");
+        const { format = "json" } = require("./formatter.js");
+        const message = require("./messages.js").hello;
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertRequire::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import "./setup.js";
+        import * as __oxjest_required_0__ from "./greeter.js";
+        const greeter = __oxjest__.interopRequire(__oxjest_required_0__);
+        import { count, "increment" as inc } from "./counter.js";
+        jest.mock("./logger.js");
+        import * as __oxjest_required_1__ from "./logger.js";
+        const logger = __oxjest__.interopRequire(__oxjest_required_1__);
+        let formatter = __oxjest__.interopRequire(await import("./formatter.js"));
+        const { format = "json" } = __oxjest__.interopRequire(await import("./formatter.js"));
+        const message = __oxjest__.interopRequire(await import("./messages.js")).hello;
+        "#);
+    }
+
+    #[test]
+    fn test_convert_require_after_statements() {
+        let source_text = r#"
+        import { join } from "node:path";
+
+        process.env.MODE = "test";
+        const config = require("./config.js");
+        require("./setup.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertRequire::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import { join } from "node:path";
+        process.env.MODE = "test";
+        const config = __oxjest__.interopRequire(await import("./config.js"));
+        await import("./setup.js");
+        "#);
+    }
+
+    #[test]
+    fn test_convert_require_module_graph() {
+        let source_text = r#"
+        const greeter = require("./greeter.ts");
+        const greet = require("./commonjsGreeter.cjs");
+        const missing = require("./missing.js");
+
+        test("greet", () => {
+            const greeter = require("./greeter.ts");
+            const greet = require("./commonjsGreeter.cjs");
+            const missing = require("./missing.js");
+        });
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let code = transform(
+            &allocator,
+            source_text,
+            ConvertRequire::new().with_module_graph(Some(&module_graph)),
+        );
+
+        insta::assert_snapshot!(code, @r#"
+        import * as greeter from "./greeter.ts";
+        import greet from "./commonjsGreeter.cjs";
+        import * as __oxjest_required_0__ from "./missing.js";
+        const missing = __oxjest__.interopRequire(__oxjest_required_0__);
+        test("greet", async () => {
+        	const greeter = await import("./greeter.ts");
+        	const greet = (await import("./commonjsGreeter.cjs")).default;
+        	const missing = __oxjest__.interopRequire(await import("./missing.js"));
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_convert_require_callbacks() {
+        let source_text = r#"
+        beforeEach(() => {
+            jest.resetModules();
+        });
+
+        test("greet", () => {
+            const { greet } = require("./greeter.js");
+            expect(greet()).toBe("Hello!");
+        });
+
+        it("count", async function () {
+            const counter = require("./counter.js");
+        });
+
+        test("isolated", async () => {
+            await jest.isolateModulesAsync(async () => {
+                require("./logger.js");
+            });
+        });
+
+        test("isolated sync", () => {
+            jest.isolateModules(() => {
+                require("./logger.js");
+            });
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ConvertRequire::new());
+
+        insta::assert_snapshot!(code, @r#"
+        beforeEach(() => {
+        	jest.resetModules();
+        });
+        test("greet", async () => {
+        	const { greet } = __oxjest__.interopRequire(await import("./greeter.js"));
+        	expect(greet()).toBe("Hello!");
+        });
+        it("count", async function() {
+        	const counter = __oxjest__.interopRequire(await import("./counter.js"));
+        });
+        test("isolated", async () => {
+        	await jest.isolateModulesAsync(async () => {
+        		await import("./logger.js");
+        	});
+        });
+        test("isolated sync", () => {
+        	jest.isolateModules(async () => {
+        		await import("./logger.js");
+        	});
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_convert_require_create_require() {
+        let source_text = r#"
+        function loadGreeter() {
+            return require("./greeter.js");
+        }
+
+        describe("greeter", () => {
+            const greeter = require("./greeter.js");
+        });
+
+        test("local require", () => {
+            const require = (id) => id;
+            require("./greeter.js");
+        });
+
+        test("sync callbacks", () => {
+            const modules = ["./greeter.js"].map(() => require("./greeter.js"));
+            setTimeout(() => require("./counter.js"));
+        });
+
+        describe("isolated", () => {
+            jest.isolateModules(() => {
+                require("./logger.js");
+            });
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let (code, convert_require) =
+            transform_with(&allocator, source_text, ConvertRequire::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const __oxjest_require__ = __oxjest__.createRequire(import.meta.url);
+        function loadGreeter() {
+        	return __oxjest_require__("./greeter.js");
+        }
+        describe("greeter", () => {
+        	const greeter = __oxjest_require__("./greeter.js");
+        });
+        test("local require", () => {
+        	const require = (id) => id;
+        	require("./greeter.js");
+        });
+        test("sync callbacks", () => {
+        	const modules = ["./greeter.js"].map(() => __oxjest_require__("./greeter.js"));
+        	setTimeout(() => __oxjest_require__("./counter.js"));
+        });
+        describe("isolated", () => {
+        	jest.isolateModules(() => {
+        		__oxjest_require__("./logger.js");
+        	});
+        });
+        "#);
+        insta::assert_snapshot!(format_errors(&convert_require.diagnostics), @r#"
+        `require` can't be converted into an await import here, thus the module is required without the ESM mocks.
+          at 53..76: Required synchronously
+        `require` can't be converted into an await import here, thus the module is required without the ESM mocks.
+          at 153..176: Required synchronously
+        `require` can't be converted into an await import here, thus the module is required without the ESM mocks.
+          at 413..436: Required synchronously
+        `require` can't be converted into an await import here, thus the module is required without the ESM mocks.
+          at 468..491: Required synchronously
+        `require` can't be converted into an await import here, thus the module is required without the ESM mocks.
+          at 600..622: Required synchronously
+        "#);
+    }
+}
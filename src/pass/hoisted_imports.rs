@@ -0,0 +1,88 @@
+use oxc::allocator::Box;
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{Expression, Statement, VariableDeclarationKind};
+use oxc::span::{Atom, Span};
+
+/// Dynamic imports to be hoisted to the top of the module, bound to the generated identifiers.
+pub(crate) struct HoistedImports<'a> {
+    prefix: &'static str,
+    modules: Vec<Atom<'a>>,
+}
+
+impl<'a> HoistedImports<'a> {
+    /// Creates an empty set of imports, named `__oxjest_{prefix}_{index}__`.
+    pub(crate) fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            modules: Vec::new(),
+        }
+    }
+
+    fn make_import_name(&self, ast: AstBuilder<'a>, index: usize) -> &'a str {
+        ast.str(&format!("__oxjest_{}_{index}__", self.prefix))
+    }
+
+    /// Registers the module to be imported, then returns an identifier referencing the module.
+    /// Modules already registered are imported once, sharing the identifier.
+    pub(crate) fn push(&mut self, ast: AstBuilder<'a>, span: Span, id: Atom<'a>) -> Expression<'a> {
+        let index = match self.modules.iter().position(|module| *module == id) {
+            Some(index) => index,
+            None => {
+                self.modules.push(id);
+                self.modules.len() - 1
+            }
+        };
+
+        ast.expression_identifier(span, self.make_import_name(ast, index))
+    }
+
+    /// Creates `const __oxjest_{prefix}_{}__ = await import(...), ...;` declaration.
+    /// Returns `None` if no modules are registered.
+    pub(crate) fn make_declaration(&self, ast: AstBuilder<'a>) -> Option<Statement<'a>> {
+        self.make_declaration_with(ast, |id| {
+            ast.expression_import(
+                Span::default(),
+                ast.expression_string_literal(Span::default(), id, None),
+                None,
+                None,
+            )
+        })
+    }
+
+    /// Same as [`Self::make_declaration`], but awaits the expressions made by `make_import` instead.
+    pub(crate) fn make_declaration_with(
+        &self,
+        ast: AstBuilder<'a>,
+        make_import: impl Fn(Atom<'a>) -> Expression<'a>,
+    ) -> Option<Statement<'a>> {
+        if self.modules.is_empty() {
+            return None;
+        }
+
+        let decl = ast.declaration_variable(
+            Span::default(),
+            VariableDeclarationKind::Const,
+            ast.vec_from_iter(self.modules.iter().enumerate().map(|(index, id)| {
+                let await_import = ast.expression_await(Span::default(), make_import(*id));
+
+                ast.variable_declarator(
+                    Span::default(),
+                    VariableDeclarationKind::Const,
+                    ast.binding_pattern(
+                        ast.binding_pattern_kind_binding_identifier(
+                            Span::default(),
+                            self.make_import_name(ast, index),
+                        ),
+                        Option::<Box<'a, _>>::None,
+                        false,
+                    ),
+                    Some(await_import),
+                    false,
+                )
+            })),
+            false,
+        );
+
+        Some(decl.into())
+    }
+}
@@ -1,79 +1,101 @@
-use oxc::allocator::Box;
+use oxc::allocator::CloneIn;
 use oxc::ast::AstBuilder;
-use oxc::ast::ast::{Argument, Expression, Program, VariableDeclarationKind};
-use oxc::span::{Atom, Span};
+use oxc::ast::ast::{Argument, Expression, Program};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::span::{Atom, GetSpanMut, Span};
 use oxc_traverse::{Traverse, TraverseCtx};
 
-use crate::jest::is_jest_require_actual_call;
+use crate::jest::{is_jest_mock_factory_callee, is_jest_require_actual_call};
+use crate::module_graph::ModuleGraph;
+use crate::pass::async_functions::{AsyncFunctions, AwaitScope};
+use crate::pass::hoisted_imports::HoistedImports;
+use crate::pass::runtime::{make_lazy_import, make_runtime_call};
 
-fn make_import_name(ast: AstBuilder<'_>, index: usize) -> &str {
-    ast.str(&format!("__oxjest_actual_{index}__"))
+/// Makes `__oxjest__.importActual(jest, "<id>", () => import("<id>"))`.
+fn make_import_actual<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    jest: Expression<'a>,
+    id: Atom<'a>,
+) -> Expression<'a> {
+    make_runtime_call(
+        ast,
+        span,
+        "importActual",
+        [
+            jest.into(),
+            ast.expression_string_literal(Span::default(), id, None)
+                .into(),
+            make_lazy_import(ast, id.as_str()).into(),
+        ],
+    )
 }
 
 /// Turn `jest.requireActual()` calls into dynamic imports, then hoists to the top of the module.
+/// The imports must be placed after the hoisted mocks, so that the actual modules import the mocked modules.
+/// The actual modules are imported through the runtime module, which unmocks them temporarily.
+///
+/// Calls directly in mock factories are turned into await imports in place instead, making the factories async.
+/// The actual modules are imported lazily when the mocked modules are imported, as in CommonJS,
+/// so that mocked modules importing each other can reference the actual modules in the factories.
 pub(crate) struct ImportActual<'a> {
-    modules: Vec<Atom<'a>>,
+    imports: HoistedImports<'a>,
+    /// The jest object referenced by the calls, to unmock the actual modules.
+    jest: Option<Expression<'a>>,
+    /// Scopes of the mock factories to make async, as they import the actual modules.
+    async_factories: AsyncFunctions,
+    /// Graph of the imported modules, to check that the actual modules are resolvable.
+    module_graph: Option<&'a ModuleGraph<'a>>,
+    pub(crate) diagnostics: Vec<OxcDiagnostic>,
 }
 
-impl ImportActual<'_> {
+impl<'a> ImportActual<'a> {
     pub(crate) fn new() -> Self {
         Self {
-            modules: Vec::new(),
+            imports: HoistedImports::new("actual"),
+            jest: None,
+            async_factories: AsyncFunctions::new(),
+            module_graph: None,
+            diagnostics: Vec::new(),
         }
     }
+
+    pub(crate) fn with_module_graph(mut self, module_graph: Option<&'a ModuleGraph<'a>>) -> Self {
+        self.module_graph = module_graph;
+        self
+    }
 }
 
 impl<'a, State> Traverse<'a, State> for ImportActual<'a> {
     fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
-        if self.modules.is_empty() {
+        let Some(jest) = &self.jest else {
             return;
-        }
+        };
 
-        // Create `const __oxjest_actual_{}__ = await import(...), ...;` declaration
-        let decl = ctx.ast.declaration_variable(
-            Span::default(),
-            VariableDeclarationKind::Const,
-            ctx.ast
-                .vec_from_iter(self.modules.iter().enumerate().map(|(index, id)| {
-                    let await_import = ctx.ast.expression_await(
-                        Span::default(),
-                        ctx.ast.expression_import(
-                            Span::default(),
-                            ctx.ast
-                                .expression_string_literal(Span::default(), *id, None),
-                            None,
-                            None,
-                        ),
-                    );
-
-                    ctx.ast.variable_declarator(
-                        Span::default(),
-                        VariableDeclarationKind::Const,
-                        ctx.ast.binding_pattern(
-                            ctx.ast.binding_pattern_kind_binding_identifier(
-                                Span::default(),
-                                make_import_name(ctx.ast, index),
-                            ),
-                            Option::<Box<'a, _>>::None,
-                            false,
-                        ),
-                        Some(await_import),
-                        false,
-                    )
-                })),
-            false,
-        );
+        // const __oxjest_actual_{}__ = await __oxjest__.importActual(jest, "<id>", () => import("<id>"));
+        let decl = self.imports.make_declaration_with(ctx.ast, |id| {
+            make_import_actual(
+                ctx.ast,
+                Span::default(),
+                jest.clone_in(ctx.ast.allocator),
+                id,
+            )
+        });
 
         // Inject the declaration at the top of the module
-        node.body.insert(0, decl.into());
+        if let Some(decl) = decl {
+            node.body.insert(0, decl);
+        }
     }
 
     fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.async_factories.exit_expression(node);
+
         let Expression::CallExpression(call) = node else {
             return;
         };
 
-        if !is_jest_require_actual_call(call) {
+        if !is_jest_require_actual_call(call, ctx.scoping()) {
             return;
         }
 
@@ -82,19 +104,46 @@ impl<'a, State> Traverse<'a, State> for ImportActual<'a> {
             return;
         };
 
-        let index = self.modules.len();
-        self.modules.push(lit.value);
+        if let Some(module_graph) = self.module_graph {
+            self.diagnostics
+                .extend(module_graph.check_resolvable(&lit.value, lit.span));
+        }
+
+        let Expression::StaticMemberExpression(member) = &call.callee else {
+            // SAFETY: Already checked above
+            unreachable!();
+        };
+        let mut jest = member.object.clone_in(ctx.ast.allocator);
+        *jest.span_mut() = Span::default();
 
-        *node = ctx
-            .ast
-            .expression_identifier(call.span, make_import_name(ctx.ast, index))
+        // await __oxjest__.importActual(jest, "<id>", () => import("<id>"))
+        if let Some(AwaitScope::Function {
+            scope_id,
+            callee: Some(callee),
+            ..
+        }) = AwaitScope::from_ctx(ctx)
+            && is_jest_mock_factory_callee(callee, ctx.scoping())
+        {
+            self.async_factories.insert(scope_id);
+            *node = ctx.ast.expression_await(
+                call.span,
+                make_import_actual(ctx.ast, Span::default(), jest, lit.value),
+            );
+            return;
+        }
+
+        if self.jest.is_none() {
+            self.jest = Some(jest);
+        }
+
+        *node = self.imports.push(ctx.ast, call.span, lit.value);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testing::transform;
+    use crate::testing::{fixture_path, format_errors, resolve, transform, transform_with};
     use oxc::allocator::Allocator;
 
     #[test]
@@ -110,11 +159,101 @@ mod tests {
         let code = transform(&allocator, source_text, ImportActual::new());
 
         insta::assert_snapshot!(code, @r#"
-        const __oxjest_actual_0__ = await import("./greeter.js");
+        jest.unstable_mockModule("./greeter.js", async () => ({
+        	...await __oxjest__.importActual(jest, "./greeter.js", () => import("./greeter.js")),
+        	greet: () => "Hello, world!"
+        }));
+        "#);
+    }
+
+    #[test]
+    fn test_import_actual_duplicate() {
+        let source_text = r#"
         jest.unstable_mockModule("./greeter.js", () => ({
-        	...__oxjest_actual_0__,
+            ...jest.requireActual("./greeter.js"),
+            greet: () => "Hello, world!",
+        }));
+
+        test("greet", () => {
+            expect(jest.requireActual("./greeter.js").greet()).toBe("Hello!");
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ImportActual::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const __oxjest_actual_0__ = await __oxjest__.importActual(jest, "./greeter.js", () => import("./greeter.js"));
+        jest.unstable_mockModule("./greeter.js", async () => ({
+        	...await __oxjest__.importActual(jest, "./greeter.js", () => import("./greeter.js")),
         	greet: () => "Hello, world!"
         }));
+        test("greet", () => {
+        	expect(__oxjest_actual_0__.greet()).toBe("Hello!");
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_import_actual_lazy() {
+        let source_text = r#"
+        jest.mock("./greeter.js", function () {
+            const actual = jest.requireActual("./greeter.js");
+            return { ...actual, greet: () => jest.requireActual("./logger.js").log("Hello!") };
+        });
+        jest.mock("./counter.js", () => ({
+            *count() {
+                yield* jest.requireActual("./counter.js").count();
+            },
+        }));
+        jest.mock("./logger.js", () => {
+            return class {
+                logger = jest.requireActual("./logger.js");
+            };
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ImportActual::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const __oxjest_actual_0__ = await __oxjest__.importActual(jest, "./logger.js", () => import("./logger.js")), __oxjest_actual_1__ = await __oxjest__.importActual(jest, "./counter.js", () => import("./counter.js"));
+        jest.mock("./greeter.js", async function() {
+        	const actual = await __oxjest__.importActual(jest, "./greeter.js", () => import("./greeter.js"));
+        	return {
+        		...actual,
+        		greet: () => __oxjest_actual_0__.log("Hello!")
+        	};
+        });
+        jest.mock("./counter.js", () => ({ *count() {
+        	yield* __oxjest_actual_1__.count();
+        } }));
+        jest.mock("./logger.js", () => {
+        	return class {
+        		logger = __oxjest_actual_0__;
+        	};
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_import_actual_unresolvable() {
+        let source_text = r#"
+        jest.unstable_mockModule("./greeter.ts", () => jest.requireActual("./greeter.ts"));
+        jest.unstable_mockModule("./missing.ts", () => jest.requireActual("./missing.ts"));
+        "#;
+
+        let module_graph = ModuleGraph::new(&resolve, fixture_path("source.test.ts"));
+        let allocator = Allocator::new();
+        let (_, import_actual) = transform_with(
+            &allocator,
+            source_text,
+            ImportActual::new().with_module_graph(Some(&module_graph)),
+        );
+
+        insta::assert_snapshot!(format_errors(&import_actual.diagnostics), @r#"
+        Cannot resolve module `./missing.ts`.
+          at 167..181: Not resolvable
         "#);
     }
 }
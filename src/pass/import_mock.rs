@@ -0,0 +1,113 @@
+use oxc::ast::ast::{Argument, Expression, Program};
+use oxc_traverse::{Traverse, TraverseCtx};
+
+use crate::jest::is_jest_require_mock_call;
+use crate::pass::async_functions::{AsyncFunctions, AwaitScope};
+use crate::pass::hoisted_imports::HoistedImports;
+
+/// Turn `jest.requireMock()` calls into dynamic imports.
+/// The calls in tests and hooks are awaited in place, so that they return the mocks registered at the time,
+/// such as by `jest.doMock` after `jest.resetModules()`. The others are hoisted to the top of the module,
+/// which must be placed after the hoisted mocks, so they can return the mocked modules.
+pub(crate) struct ImportMock<'a> {
+    imports: HoistedImports<'a>,
+    async_functions: AsyncFunctions,
+}
+
+impl ImportMock<'_> {
+    pub(crate) fn new() -> Self {
+        Self {
+            imports: HoistedImports::new("mock"),
+            async_functions: AsyncFunctions::new(),
+        }
+    }
+}
+
+impl<'a, State> Traverse<'a, State> for ImportMock<'a> {
+    fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        // Inject the declaration at the top of the module
+        if let Some(decl) = self.imports.make_declaration(ctx.ast) {
+            node.body.insert(0, decl);
+        }
+    }
+
+    fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.async_functions.exit_expression(node);
+
+        let Expression::CallExpression(call) = node else {
+            return;
+        };
+
+        if !is_jest_require_mock_call(call, ctx.scoping()) {
+            return;
+        }
+
+        // jest.requireMock("<id>");
+        let Some(Argument::StringLiteral(lit)) = call.arguments.first() else {
+            return;
+        };
+
+        // await import("<id>")
+        if let Some(scope @ AwaitScope::Function { scope_id, .. }) = AwaitScope::from_ctx(ctx)
+            && scope.is_awaitable()
+        {
+            self.async_functions.insert(scope_id);
+            *node = ctx.ast.expression_await(
+                call.span,
+                ctx.ast.expression_import(
+                    call.span,
+                    ctx.ast
+                        .expression_string_literal(lit.span, lit.value, lit.raw),
+                    None,
+                    None,
+                ),
+            );
+            return;
+        }
+
+        *node = self.imports.push(ctx.ast, call.span, lit.value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::transform;
+    use oxc::allocator::Allocator;
+
+    #[test]
+    fn test_import_mock() {
+        let source_text = r#"
+        jest.mock("./greeter.js");
+
+        const { greet } = jest.requireMock("./greeter.js");
+
+        function helper() {
+            return jest.requireMock("./greeter.js");
+        }
+
+        test("greet", () => {
+            jest.resetModules();
+            jest.doMock("./greeter.js");
+            const greeter = jest.requireMock("./greeter.js");
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, ImportMock::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const __oxjest_mock_0__ = await import("./greeter.js");
+        jest.mock("./greeter.js");
+        const { greet } = __oxjest_mock_0__;
+        function helper() {
+        	return __oxjest_mock_0__;
+        }
+        test("greet", async () => {
+        	jest.resetModules();
+        	jest.doMock("./greeter.js");
+        	const greeter = await import("./greeter.js");
+        });
+        "#);
+    }
+}
@@ -1,15 +1,18 @@
 use oxc::allocator::Box;
 use oxc::ast::AstBuilder;
 use oxc::ast::ast::{
-    Expression, IdentifierReference, ImportOrExportKind, MemberExpression, Program, Statement,
-    StaticMemberExpression,
+    Expression, IdentifierReference, ImportDeclarationSpecifier, ImportOrExportKind,
+    MemberExpression, Program, Statement, StaticMemberExpression,
 };
+use oxc::semantic::SymbolId;
 use oxc::span::Span;
 use oxc_traverse::{Traverse, TraverseCtx};
 
+use crate::pass::runtime::OXJEST_RUNTIME_NAME;
+
 const OXJEST_RUNTIME_ID: &str = "oxjest/runtime";
-const OXJEST_RUNTIME_NAME: &str = "__oxjest__";
 const JEST_OBJECT_NAME: &str = "jest";
+const JEST_GLOBALS_ID: &str = "@jest/globals";
 
 fn make_runtime_import_stmt<'a>(ast: AstBuilder<'a>) -> Statement<'a> {
     Statement::ImportDeclaration(ast.alloc_import_declaration::<Option<Box<'a, _>>>(
@@ -40,45 +43,89 @@ fn make_import_meta_jest<'a>(
             false,
         ))
         .into(),
-        ast.identifier_name(reference.span, reference.name),
+        ast.identifier_name(reference.span, JEST_OBJECT_NAME),
         false,
     )
 }
 
-pub(crate) struct InjectGlobals {}
+/// Turns references to the global `jest` object into `import.meta.jest`.
+/// Both member accesses (`jest.fn()`) and bare references (`helper(jest)`) are covered.
+pub(crate) struct InjectGlobals {
+    /// Symbols bound by `import { jest } from "@jest/globals"`.
+    jest_imports: Vec<SymbolId>,
+}
 
 impl InjectGlobals {
     pub(crate) fn new() -> Self {
-        Self {}
+        Self {
+            jest_imports: Vec::new(),
+        }
+    }
+
+    /// Checks that the identifier refers to the jest object, rather than a local binding named `jest`.
+    fn is_jest_reference<State>(
+        &self,
+        ident: &IdentifierReference<'_>,
+        ctx: &TraverseCtx<'_, State>,
+    ) -> bool {
+        let Some(reference_id) = ident.reference_id.get() else {
+            return false;
+        };
+
+        match ctx.scoping().get_reference(reference_id).symbol_id() {
+            // Unresolved references are the globals
+            None => ident.name == JEST_OBJECT_NAME,
+            // Imported jest objects are normalized too, as they are not available above hoisted mocks
+            Some(symbol_id) => self.jest_imports.contains(&symbol_id),
+        }
     }
 }
 
 impl<'a, State> Traverse<'a, State> for InjectGlobals {
+    fn enter_program(&mut self, node: &mut Program<'a>, _ctx: &mut TraverseCtx<'a, State>) {
+        for stmt in &node.body {
+            let Statement::ImportDeclaration(decl) = stmt else {
+                continue;
+            };
+
+            if decl.source.value != JEST_GLOBALS_ID {
+                continue;
+            }
+
+            self.jest_imports
+                .extend(
+                    decl.specifiers
+                        .iter()
+                        .flatten()
+                        .filter_map(|specifier| match specifier {
+                            ImportDeclarationSpecifier::ImportSpecifier(specifier)
+                                if specifier.imported.name() == JEST_OBJECT_NAME =>
+                            {
+                                specifier.local.symbol_id.get()
+                            }
+                            _ => None,
+                        }),
+                );
+        }
+    }
+
     fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
         node.body.insert(0, make_runtime_import_stmt(ctx.ast));
     }
 
-    fn enter_member_expression(
-        &mut self,
-        node: &mut MemberExpression<'a>,
-        ctx: &mut TraverseCtx<'a, State>,
-    ) {
-        let MemberExpression::StaticMemberExpression(expr) = node else {
-            return;
-        };
-
-        let Expression::Identifier(ident) = &expr.object else {
+    fn enter_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        let Expression::Identifier(ident) = node else {
             return;
         };
 
-        if ident.name != JEST_OBJECT_NAME {
+        if !self.is_jest_reference(ident, ctx) {
             return;
         }
 
-        expr.object = MemberExpression::StaticMemberExpression(
-            ctx.ast.alloc(make_import_meta_jest(ctx.ast, ident)),
-        )
-        .into();
+        let import_meta_jest = make_import_meta_jest(ctx.ast, ident);
+        ctx.delete_reference_for_identifier(ident);
+
+        *node = Expression::StaticMemberExpression(ctx.ast.alloc(import_meta_jest));
     }
 }
 
@@ -104,4 +151,75 @@ mod tests {
         import.meta.jest.mock("./greeter.js");
         "#);
     }
+
+    #[test]
+    fn test_inject_globals_bare_reference() {
+        let source_text = r#"
+        // all of them need to be import.meta.jest
+        const { fn, spyOn } = jest;
+        helper(jest);
+        if (typeof jest !== "undefined") {
+            setup({ jest });
+        }
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, InjectGlobals::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import * as __oxjest__ from "oxjest/runtime";
+        // all of them need to be import.meta.jest
+        const { fn, spyOn } = import.meta.jest;
+        helper(import.meta.jest);
+        if (typeof import.meta.jest !== "undefined") {
+        	setup({ jest: import.meta.jest });
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_inject_globals_local_binding() {
+        let source_text = r#"
+        // these are not the jest object
+        const jest = { fn: () => {} };
+        jest.fn();
+
+        function run(jest) {
+            jest.fn();
+        }
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, InjectGlobals::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import * as __oxjest__ from "oxjest/runtime";
+        // these are not the jest object
+        const jest = { fn: () => {} };
+        jest.fn();
+        function run(jest) {
+        	jest.fn();
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_inject_globals_jest_globals_import() {
+        let source_text = r#"
+        import { jest as j } from "@jest/globals";
+
+        // needs to be import.meta.jest, as well as the global one
+        j.mock("./greeter.js");
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, InjectGlobals::new());
+
+        insta::assert_snapshot!(code, @r#"
+        import * as __oxjest__ from "oxjest/runtime";
+        import { jest as j } from "@jest/globals";
+        // needs to be import.meta.jest, as well as the global one
+        import.meta.jest.mock("./greeter.js");
+        "#);
+    }
 }
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use oxc::ast::ast::{
+    Argument, BindingPatternKind, Expression, Program, VariableDeclaration, VariableDeclarationKind,
+};
+use oxc::ast_visit::{Visit, walk};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::semantic::{Scoping, SymbolId};
+use oxc::span::{Atom, GetSpan};
+use oxc_traverse::{Traverse, TraverseCtx};
+
+use crate::jest::is_jest_module_call;
+
+/// Returns the string of `"..."` or `` `...` `` without expressions.
+fn as_static_string<'a>(expr: &Expression<'a>) -> Option<Atom<'a>> {
+    match expr {
+        Expression::StringLiteral(lit) => Some(lit.value),
+        Expression::TemplateLiteral(lit) if lit.expressions.is_empty() => lit.single_quasi(),
+        _ => None,
+    }
+}
+
+/// Collects `const` variables bound to static strings.
+struct ConstStrings<'a> {
+    strings: HashMap<SymbolId, Atom<'a>>,
+}
+
+impl<'a> Visit<'a> for ConstStrings<'a> {
+    fn visit_variable_declaration(&mut self, decl: &VariableDeclaration<'a>) {
+        if decl.kind == VariableDeclarationKind::Const {
+            for declarator in &decl.declarations {
+                if let BindingPatternKind::BindingIdentifier(ident) = &declarator.id.kind
+                    && let Some(symbol_id) = ident.symbol_id.get()
+                    && let Some(string) = declarator.init.as_ref().and_then(as_static_string)
+                {
+                    self.strings.insert(symbol_id, string);
+                }
+            }
+        }
+
+        walk::walk_variable_declaration(self, decl);
+    }
+}
+
+/// Turns the module specifiers of jest methods, such as `` jest.mock(`./greeter`) `` or `jest.mock(GREETER)` with
+/// `const GREETER = "./greeter"`, into string literals, which the other transforms can handle.
+pub(crate) struct InlineSpecifiers<'a> {
+    /// Static strings bound to `const` variables, which can be referenced as module specifiers.
+    const_strings: HashMap<SymbolId, Atom<'a>>,
+    pub(crate) diagnostics: Vec<OxcDiagnostic>,
+}
+
+impl<'a> InlineSpecifiers<'a> {
+    pub(crate) fn new() -> Self {
+        Self {
+            const_strings: HashMap::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Returns the static string of the module specifier, or `None` if it can't be determined.
+    fn resolve_specifier(&self, expr: &Expression<'a>, scoping: &Scoping) -> Option<Atom<'a>> {
+        if let Expression::Identifier(ident) = expr {
+            let symbol_id = scoping
+                .get_reference(ident.reference_id.get()?)
+                .symbol_id()?;
+
+            return self.const_strings.get(&symbol_id).copied();
+        }
+
+        as_static_string(expr)
+    }
+}
+
+impl<'a, State> Traverse<'a, State> for InlineSpecifiers<'a> {
+    fn enter_program(&mut self, node: &mut Program<'a>, _ctx: &mut TraverseCtx<'a, State>) {
+        let mut const_strings = ConstStrings {
+            strings: HashMap::new(),
+        };
+        const_strings.visit_program(node);
+
+        self.const_strings = const_strings.strings;
+    }
+
+    fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        let Expression::CallExpression(call) = node else {
+            return;
+        };
+
+        if !is_jest_module_call(call, ctx.scoping()) {
+            return;
+        }
+
+        // jest.mock("<id>") is already a string literal, and jest.mock() fails on its own
+        let Some(expr) = call.arguments.first().and_then(Argument::as_expression) else {
+            return;
+        };
+        if matches!(expr, Expression::StringLiteral(_)) {
+            return;
+        }
+
+        let span = expr.span();
+        let Some(specifier) = self.resolve_specifier(expr, ctx.scoping()) else {
+            let Expression::StaticMemberExpression(member) = &call.callee else {
+                // SAFETY: Already checked above
+                unreachable!();
+            };
+
+            self.diagnostics.push(
+                OxcDiagnostic::warn(format!(
+                    "The module specifier of `jest.{}` can't be determined statically, thus it's left untransformed.",
+                    member.property.name
+                ))
+                .with_label(span.label("Not a static string"))
+                .with_help("Use a string literal, a template literal without expressions, or a `const` variable bound to one of them."),
+            );
+            return;
+        };
+
+        call.arguments[0] =
+            Argument::StringLiteral(ctx.ast.alloc_string_literal(span, specifier, None));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{format_errors, transform, transform_with};
+    use oxc::allocator::Allocator;
+
+    #[test]
+    fn test_inline_specifiers() {
+        let source_text = r#"
+        const GREETER = "./greeter.js";
+        const LOGGER = `./logger.js`;
+
+        jest.mock(`./counter.js`);
+        jest.mock(GREETER, () => ({ greet: jest.fn() }));
+        jest.unmock(LOGGER);
+
+        test("greet", () => {
+            const greeter = jest.requireActual(GREETER);
+            const logger = jest.requireMock(LOGGER);
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, InlineSpecifiers::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const GREETER = "./greeter.js";
+        const LOGGER = `./logger.js`;
+        jest.mock("./counter.js");
+        jest.mock("./greeter.js", () => ({ greet: jest.fn() }));
+        jest.unmock("./logger.js");
+        test("greet", () => {
+        	const greeter = jest.requireActual("./greeter.js");
+        	const logger = jest.requireMock("./logger.js");
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_inline_specifiers_dynamic() {
+        let source_text = r#"
+        let greeter = "./greeter.js";
+        const counter = "./" + "counter.js";
+
+        jest.mock(greeter);
+        jest.mock(`./${counter}`);
+        jest.doMock(counter, () => ({}));
+        jest.requireActual(require.resolve("./logger.js"));
+
+        test("greet", () => {
+            const GREETER = "./greeter.js";
+            jest.mock(GREETER);
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let (_, inline_specifiers) =
+            transform_with(&allocator, source_text, InlineSpecifiers::new());
+
+        insta::assert_snapshot!(format_errors(&inline_specifiers.diagnostics), @r#"
+        The module specifier of `jest.mock` can't be determined statically, thus it's left untransformed.
+          at 103..110: Not a static string
+        The module specifier of `jest.mock` can't be determined statically, thus it's left untransformed.
+          at 131..145: Not a static string
+        The module specifier of `jest.doMock` can't be determined statically, thus it's left untransformed.
+          at 168..175: Not a static string
+        The module specifier of `jest.requireActual` can't be determined statically, thus it's left untransformed.
+          at 217..247: Not a static string
+        "#);
+    }
+}
@@ -0,0 +1,176 @@
+use oxc::allocator::TakeIn;
+use oxc::ast::ast::{Argument, Expression};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc_traverse::{Traverse, TraverseCtx};
+
+use crate::jest::is_jest_isolate_modules_call;
+use crate::pass::async_functions::{AsyncFunctions, AwaitScope};
+
+/// Turns `jest.isolateModules(() => {})` into `await jest.isolateModulesAsync(async () => {})`,
+/// so that the callbacks can import modules with await imports, as `require` is not available in ESM.
+/// The test and hook callbacks calling `jest.isolateModules` are made async too.
+pub(crate) struct IsolateModules {
+    async_functions: AsyncFunctions,
+    pub(crate) diagnostics: Vec<OxcDiagnostic>,
+}
+
+impl IsolateModules {
+    pub(crate) fn new() -> Self {
+        Self {
+            async_functions: AsyncFunctions::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl<'a, State> Traverse<'a, State> for IsolateModules {
+    fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.async_functions.exit_expression(node);
+
+        let Expression::CallExpression(call) = node else {
+            return;
+        };
+
+        if !is_jest_isolate_modules_call(call, ctx.scoping()) {
+            return;
+        }
+
+        match AwaitScope::from_ctx(ctx) {
+            Some(scope) if scope.is_awaitable() => {
+                if let AwaitScope::Function { scope_id, .. } = scope {
+                    self.async_functions.insert(scope_id);
+                }
+            }
+            _ => {
+                self.diagnostics.push(
+                    OxcDiagnostic::warn(
+                        "`jest.isolateModules` can't be awaited here, thus it's left untransformed.",
+                    )
+                    .with_label(call.span.label("Not awaitable"))
+                    .with_help(
+                        "Call `jest.isolateModules` in a test or a hook, or use `jest.isolateModulesAsync` instead.",
+                    ),
+                );
+                return;
+            }
+        }
+
+        // jest.isolateModules(() => {}) -> await jest.isolateModulesAsync(async () => {})
+        let Expression::StaticMemberExpression(member) = &mut call.callee else {
+            // SAFETY: Already checked above
+            unreachable!();
+        };
+        member.property.name = "isolateModulesAsync".into();
+
+        match call.arguments.first_mut() {
+            Some(Argument::ArrowFunctionExpression(func)) => func.r#async = true,
+            Some(Argument::FunctionExpression(func)) if !func.generator => func.r#async = true,
+            _ => {}
+        }
+
+        let span = call.span;
+        *node = ctx
+            .ast
+            .expression_await(span, node.take_in(ctx.ast.allocator));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{format_errors, transform, transform_with};
+    use oxc::allocator::Allocator;
+
+    #[test]
+    fn test_isolate_modules() {
+        let source_text = r#"
+        test("isolated", () => {
+            jest.isolateModules(() => {
+                jest.mock("./greeter.js");
+            });
+        });
+
+        it("isolated in a function", function () {
+            jest.isolateModules(function () {
+                jest.mock("./counter.js");
+            });
+        });
+
+        beforeEach(async () => {
+            jest.isolateModules(setup);
+        });
+
+        jest.isolateModules(() => {});
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, IsolateModules::new());
+
+        insta::assert_snapshot!(code, @r#"
+        test("isolated", async () => {
+        	await jest.isolateModulesAsync(async () => {
+        		jest.mock("./greeter.js");
+        	});
+        });
+        it("isolated in a function", async function() {
+        	await jest.isolateModulesAsync(async function() {
+        		jest.mock("./counter.js");
+        	});
+        });
+        beforeEach(async () => {
+        	await jest.isolateModulesAsync(setup);
+        });
+        await jest.isolateModulesAsync(async () => {});
+        "#);
+    }
+
+    #[test]
+    fn test_isolate_modules_untransformed() {
+        let source_text = r#"
+        describe("isolated", () => {
+            jest.isolateModules(() => {});
+        });
+
+        function* isolated() {
+            jest.isolateModules(() => {});
+        }
+
+        function helper() {
+            jest.isolateModules(() => {});
+        }
+
+        ["./greeter.js"].forEach(() => {
+            jest.isolateModules(() => {});
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let (code, isolate_modules) =
+            transform_with(&allocator, source_text, IsolateModules::new());
+
+        insta::assert_snapshot!(code, @r#"
+        describe("isolated", () => {
+        	jest.isolateModules(() => {});
+        });
+        function* isolated() {
+        	jest.isolateModules(() => {});
+        }
+        function helper() {
+        	jest.isolateModules(() => {});
+        }
+        ["./greeter.js"].forEach(() => {
+        	jest.isolateModules(() => {});
+        });
+        "#);
+        insta::assert_snapshot!(format_errors(&isolate_modules.diagnostics), @r#"
+        `jest.isolateModules` can't be awaited here, thus it's left untransformed.
+          at 50..79: Not awaitable
+        `jest.isolateModules` can't be awaited here, thus it's left untransformed.
+          at 137..166: Not awaitable
+        `jest.isolateModules` can't be awaited here, thus it's left untransformed.
+          at 219..248: Not awaitable
+        `jest.isolateModules` can't be awaited here, thus it's left untransformed.
+          at 314..343: Not awaitable
+        "#);
+    }
+}
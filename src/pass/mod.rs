@@ -1,47 +1,134 @@
 use oxc::allocator::Vec;
-use oxc::ast::ast::{Expression, MemberExpression, Program, Statement};
+use oxc::ast::ast::{Expression, Program, Statement};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::span::SourceType;
 use oxc_traverse::{Traverse, TraverseCtx};
 
+use crate::module_graph::{ModuleGraph, Resolver};
+
+pub(crate) mod async_functions;
 pub(crate) mod convert_mocks;
+pub(crate) mod convert_require;
+pub(crate) mod hoisted_imports;
 pub(crate) mod import_actual;
+pub(crate) mod import_mock;
 pub(crate) mod inject_globals;
+pub(crate) mod inline_specifiers;
+pub(crate) mod isolate_modules;
+pub(crate) mod polyfill_paths;
+pub(crate) mod runtime;
+
+/// Options for the transforms.
+pub(crate) struct TransformerOptions<'r> {
+    /// Whether to keep the live bindings of the imports converted into dynamic imports.
+    pub(crate) live_bindings: bool,
+    /// Resolver of the imported modules, to match the mocked modules with the imports.
+    pub(crate) resolver: Option<&'r dyn Resolver>,
+    /// Whether to convert all imports in modules with hoisted mocks, even if a resolver is given.
+    pub(crate) convert_all_imports: bool,
+    /// Whether to report the warnings as errors, failing the transform.
+    pub(crate) strict: bool,
+    /// Source type of the module if it can't be inferred from the extension.
+    pub(crate) source_type: Option<SourceType>,
+}
+
+impl Default for TransformerOptions<'_> {
+    fn default() -> Self {
+        Self {
+            live_bindings: true,
+            resolver: None,
+            convert_all_imports: false,
+            strict: false,
+            source_type: None,
+        }
+    }
+}
 
 /// The facade of all transforms combined into one.
 /// Be careful with calling order when adding a new transform.
 pub(crate) struct Transformer<'a> {
+    inline_specifiers: inline_specifiers::InlineSpecifiers<'a>,
     convert_mocks: convert_mocks::ConvertMocks<'a>,
     import_actual: import_actual::ImportActual<'a>,
+    import_mock: import_mock::ImportMock<'a>,
+    isolate_modules: isolate_modules::IsolateModules,
+    convert_require: convert_require::ConvertRequire<'a>,
+    polyfill_paths: polyfill_paths::PolyfillPaths,
     inject_globals: inject_globals::InjectGlobals,
 }
 
-impl Transformer<'_> {
-    pub(crate) fn new() -> Self {
+impl<'a> Transformer<'a> {
+    pub(crate) fn new(
+        options: &TransformerOptions,
+        module_graph: Option<&'a ModuleGraph<'a>>,
+    ) -> Self {
         Self {
-            convert_mocks: convert_mocks::ConvertMocks::new(),
-            import_actual: import_actual::ImportActual::new(),
+            inline_specifiers: inline_specifiers::InlineSpecifiers::new(),
+            convert_mocks: convert_mocks::ConvertMocks::new()
+                .with_live_bindings(options.live_bindings)
+                .with_module_graph(module_graph)
+                .with_convert_all_imports(options.convert_all_imports),
+            import_actual: import_actual::ImportActual::new().with_module_graph(module_graph),
+            import_mock: import_mock::ImportMock::new(),
+            isolate_modules: isolate_modules::IsolateModules::new(),
+            convert_require: convert_require::ConvertRequire::new().with_module_graph(module_graph),
+            polyfill_paths: polyfill_paths::PolyfillPaths::new(),
             inject_globals: inject_globals::InjectGlobals::new(),
         }
     }
+
+    /// Consumes the transformer, returning the diagnostics reported by the transforms.
+    pub(crate) fn into_diagnostics(self) -> std::vec::Vec<OxcDiagnostic> {
+        let mut diagnostics = self.inline_specifiers.diagnostics;
+        diagnostics.extend(self.convert_mocks.diagnostics);
+        diagnostics.extend(self.import_actual.diagnostics);
+        diagnostics.extend(self.isolate_modules.diagnostics);
+        diagnostics.extend(self.convert_require.diagnostics);
+        diagnostics
+    }
 }
 
 impl<'a, State> Traverse<'a, State> for Transformer<'a> {
+    fn enter_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.inline_specifiers.enter_program(node, ctx);
+        // Top-level `require` calls are turned into imports, which may be converted by the other transforms
+        self.convert_require.enter_program(node, ctx);
+        self.inject_globals.enter_program(node, ctx);
+    }
+
     fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
-        self.convert_mocks.exit_program(node, ctx);
+        // Imports of the mocked and actual modules need to be placed after the hoisted mocks,
+        // and the actual modules before the mocked ones, as the mock factories may reference them
+        self.import_mock.exit_program(node, ctx);
         self.import_actual.exit_program(node, ctx);
+        self.convert_mocks.exit_program(node, ctx);
+        // `require` and the paths are declared above everything, as the mock factories may reference them
+        self.convert_require.exit_program(node, ctx);
+        self.polyfill_paths.exit_program(node, ctx);
         self.inject_globals.exit_program(node, ctx);
     }
 
     fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        // Module specifiers need to be string literals before the other transforms see them
+        self.inline_specifiers.exit_expression(node, ctx);
         self.convert_mocks.exit_expression(node, ctx);
         self.import_actual.exit_expression(node, ctx);
+        self.import_mock.exit_expression(node, ctx);
+        self.isolate_modules.exit_expression(node, ctx);
+        self.convert_require.exit_expression(node, ctx);
+        self.polyfill_paths.exit_expression(node, ctx);
+    }
+
+    fn enter_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        self.inject_globals.enter_expression(node, ctx);
     }
 
-    fn enter_member_expression(
+    fn enter_statements(
         &mut self,
-        node: &mut MemberExpression<'a>,
+        node: &mut Vec<'a, Statement<'a>>,
         ctx: &mut TraverseCtx<'a, State>,
     ) {
-        self.inject_globals.enter_member_expression(node, ctx);
+        self.convert_mocks.enter_statements(node, ctx);
     }
 
     fn exit_statements(
@@ -0,0 +1,214 @@
+use oxc::allocator::Box;
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{Expression, Program, Statement, VariableDeclarationKind};
+use oxc::span::{GetSpan, Span};
+use oxc_traverse::{Traverse, TraverseCtx};
+
+use crate::pass::runtime::{make_import_meta_url, make_runtime_call};
+
+/// Paths of the module, which are not available in ESM, or in Jest before v30 for `import.meta`.
+#[derive(Clone, Copy, PartialEq)]
+enum ModulePath {
+    /// `__filename` or `import.meta.filename`.
+    Filename,
+    /// `__dirname` or `import.meta.dirname`.
+    Dirname,
+}
+
+impl ModulePath {
+    /// Name of the variable declared for the path.
+    fn variable_name(self) -> &'static str {
+        match self {
+            Self::Filename => "__oxjest_filename__",
+            Self::Dirname => "__oxjest_dirname__",
+        }
+    }
+
+    /// Name of the runtime function computing the path from `import.meta.url`.
+    fn runtime_method(self) -> &'static str {
+        match self {
+            Self::Filename => "filename",
+            Self::Dirname => "dirname",
+        }
+    }
+
+    /// Returns the path referenced by `__filename` or `__dirname`.
+    fn from_global_name(name: &str) -> Option<Self> {
+        match name {
+            "__filename" => Some(Self::Filename),
+            "__dirname" => Some(Self::Dirname),
+            _ => None,
+        }
+    }
+
+    /// Returns the path referenced by `import.meta.filename` or `import.meta.dirname`.
+    fn from_import_meta(expr: &Expression) -> Option<Self> {
+        let Expression::StaticMemberExpression(member) = expr else {
+            return None;
+        };
+        let Expression::MetaProperty(meta) = &member.object else {
+            return None;
+        };
+        if meta.meta.name != "import" || meta.property.name != "meta" {
+            return None;
+        }
+
+        match member.property.name.as_str() {
+            "filename" => Some(Self::Filename),
+            "dirname" => Some(Self::Dirname),
+            _ => None,
+        }
+    }
+}
+
+/// Makes `const __oxjest_dirname__ = __oxjest__.dirname(import.meta.url);`.
+fn make_declaration<'a>(ast: AstBuilder<'a>, path: ModulePath) -> Statement<'a> {
+    Statement::from(ast.declaration_variable(
+        Span::default(),
+        VariableDeclarationKind::Const,
+        ast.vec1(ast.variable_declarator(
+            Span::default(),
+            VariableDeclarationKind::Const,
+            ast.binding_pattern(
+                ast.binding_pattern_kind_binding_identifier(Span::default(), path.variable_name()),
+                Option::<Box<'a, _>>::None,
+                false,
+            ),
+            Some(make_runtime_call(
+                ast,
+                Span::default(),
+                path.runtime_method(),
+                [make_import_meta_url(ast).into()],
+            )),
+            false,
+        )),
+        false,
+    ))
+}
+
+/// Turns references to the global `__dirname` and `__filename`, and `import.meta.dirname` and `import.meta.filename`
+/// into the variables declared at the top of the module, which are computed from `import.meta.url`.
+/// The variables are declared only if referenced.
+pub(crate) struct PolyfillPaths {
+    /// Paths referenced in the module, in the order of first reference.
+    paths: Vec<ModulePath>,
+}
+
+impl PolyfillPaths {
+    pub(crate) fn new() -> Self {
+        Self { paths: Vec::new() }
+    }
+
+    fn make_reference<'a>(
+        &mut self,
+        ast: AstBuilder<'a>,
+        span: Span,
+        path: ModulePath,
+    ) -> Expression<'a> {
+        if !self.paths.contains(&path) {
+            self.paths.push(path);
+        }
+
+        ast.expression_identifier(span, path.variable_name())
+    }
+}
+
+impl<'a, State> Traverse<'a, State> for PolyfillPaths {
+    fn exit_program(&mut self, node: &mut Program<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        // Declared above everything, as the hoisted mocks may reference them
+        node.body.splice(
+            0..0,
+            self.paths
+                .iter()
+                .map(|path| make_declaration(ctx.ast, *path)),
+        );
+    }
+
+    fn exit_expression(&mut self, node: &mut Expression<'a>, ctx: &mut TraverseCtx<'a, State>) {
+        if let Some(path) = ModulePath::from_import_meta(node) {
+            *node = self.make_reference(ctx.ast, node.span(), path);
+            return;
+        }
+
+        let Expression::Identifier(ident) = node else {
+            return;
+        };
+        let Some(path) = ModulePath::from_global_name(&ident.name) else {
+            return;
+        };
+
+        // Unresolved references are the globals, while local variables may be named `__dirname`
+        let Some(reference_id) = ident.reference_id.get() else {
+            return;
+        };
+        if ctx
+            .scoping()
+            .get_reference(reference_id)
+            .symbol_id()
+            .is_some()
+        {
+            return;
+        }
+
+        ctx.delete_reference_for_identifier(ident);
+        *node = self.make_reference(ctx.ast, ident.span, path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::transform;
+    use oxc::allocator::Allocator;
+
+    #[test]
+    fn test_polyfill_paths() {
+        let source_text = r#"
+        import { join } from "node:path";
+
+        const fixture = join(__dirname, "fixture.json");
+
+        test("paths", () => {
+            expect(import.meta.filename).toBe(__filename);
+            expect(import.meta.dirname).toBe(__dirname);
+            expect(import.meta.url).toBeDefined();
+        });
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, PolyfillPaths::new());
+
+        insta::assert_snapshot!(code, @r#"
+        const __oxjest_dirname__ = __oxjest__.dirname(import.meta.url);
+        const __oxjest_filename__ = __oxjest__.filename(import.meta.url);
+        import { join } from "node:path";
+        const fixture = join(__oxjest_dirname__, "fixture.json");
+        test("paths", () => {
+        	expect(__oxjest_filename__).toBe(__oxjest_filename__);
+        	expect(__oxjest_dirname__).toBe(__oxjest_dirname__);
+        	expect(import.meta.url).toBeDefined();
+        });
+        "#);
+    }
+
+    #[test]
+    fn test_polyfill_paths_local() {
+        let source_text = r#"
+        function resolve(__dirname, file) {
+            return __dirname + "/" + file;
+        }
+
+        const __filename = "greeter.js";
+        "#;
+
+        let allocator = Allocator::new();
+        let code = transform(&allocator, source_text, PolyfillPaths::new());
+
+        insta::assert_snapshot!(code, @r#"
+        function resolve(__dirname, file) {
+        	return __dirname + "/" + file;
+        }
+        const __filename = "greeter.js";
+        "#);
+    }
+}
@@ -0,0 +1,73 @@
+use oxc::allocator::Box;
+use oxc::ast::AstBuilder;
+use oxc::ast::ast::{Argument, Expression, FormalParameterKind, Span};
+
+/// Name of the runtime module injected into every module.
+pub(crate) const OXJEST_RUNTIME_NAME: &str = "__oxjest__";
+
+/// Makes `__oxjest__.<method>(...)`, calling the runtime module injected into every module.
+pub(crate) fn make_runtime_call<'a>(
+    ast: AstBuilder<'a>,
+    span: Span,
+    method: &'static str,
+    arguments: impl IntoIterator<Item = Argument<'a>>,
+) -> Expression<'a> {
+    ast.expression_call(
+        span,
+        ast.member_expression_static(
+            Span::default(),
+            ast.expression_identifier(Span::default(), OXJEST_RUNTIME_NAME),
+            ast.identifier_name(Span::default(), method),
+            false,
+        )
+        .into(),
+        Option::<Box<'_, _>>::None,
+        ast.vec_from_iter(arguments),
+        false,
+    )
+}
+
+/// Makes `() => import(id)`, which imports the module relative to the current module when it's called.
+pub(crate) fn make_lazy_import<'a>(ast: AstBuilder<'a>, id: &'a str) -> Expression<'a> {
+    ast.expression_arrow_function(
+        Span::default(),
+        true,
+        false,
+        Option::<Box<'_, _>>::None,
+        ast.formal_parameters(
+            Span::default(),
+            FormalParameterKind::ArrowFormalParameters,
+            ast.vec(),
+            Option::<Box<'_, _>>::None,
+        ),
+        Option::<Box<'_, _>>::None,
+        ast.function_body(
+            Span::default(),
+            ast.vec(),
+            ast.vec1(ast.statement_expression(
+                Span::default(),
+                ast.expression_import(
+                    Span::default(),
+                    ast.expression_string_literal(Span::default(), id, None),
+                    None,
+                    None,
+                ),
+            )),
+        ),
+    )
+}
+
+/// Makes `import.meta.url`, which the runtime module computes the paths of the module from.
+pub(crate) fn make_import_meta_url<'a>(ast: AstBuilder<'a>) -> Expression<'a> {
+    ast.member_expression_static(
+        Span::default(),
+        ast.expression_meta_property(
+            Span::default(),
+            ast.identifier_name(Span::default(), "import"),
+            ast.identifier_name(Span::default(), "meta"),
+        ),
+        ast.identifier_name(Span::default(), "url"),
+        false,
+    )
+    .into()
+}
@@ -1,8 +1,10 @@
-use std::path::PathBuf;
+use std::iter::once;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use oxc::allocator::Allocator;
 use oxc::codegen::{Codegen, CodegenReturn};
+use oxc::diagnostics::OxcDiagnostic;
 use oxc_traverse::{Traverse, traverse_mut};
 
 use crate::loader::Loader;
@@ -10,20 +12,61 @@ use crate::loader::Loader;
 pub(crate) fn transform<'a>(
     allocator: &'a Allocator,
     source_text: &str,
-    mut traverser: impl Traverse<'a, ()>,
+    traverser: impl Traverse<'a, ()>,
 ) -> String {
+    let (code, _) = transform_with(allocator, source_text, traverser);
+    code
+}
+
+/// Same as [`transform`], but returns the traverser as well to inspect its state after transforming.
+pub(crate) fn transform_with<'a, T: Traverse<'a, ()>>(
+    allocator: &'a Allocator,
+    source_text: &str,
+    mut traverser: T,
+) -> (String, T) {
     let source_path = PathBuf::from_str("/path/to/source.js").unwrap();
 
     let source_text = allocator.alloc_str(source_text);
     let (mut program, scoping) = Loader
-        .load_str(&allocator, source_text, &source_path)
+        .load_str(allocator, source_text, &source_path, None)
         .unwrap();
 
-    traverse_mut(&mut traverser, &allocator, &mut program, scoping, ());
+    traverse_mut(&mut traverser, allocator, &mut program, scoping, ());
 
     let CodegenReturn { code, .. } = Codegen::new()
         .with_options(Default::default())
         .build(&program);
 
-    code
+    (code, traverser)
+}
+
+/// Formats the diagnostics with their labels, for snapshot testing.
+pub(crate) fn format_errors(errors: &[OxcDiagnostic]) -> String {
+    errors
+        .iter()
+        .flat_map(|error| {
+            once(error.message.to_string()).chain(error.labels.iter().flatten().map(|label| {
+                format!(
+                    "  at {}..{}: {}",
+                    label.offset(),
+                    label.offset() + label.len(),
+                    label.label().unwrap_or_default(),
+                )
+            }))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolves the specifier relative to the importing module, as a resolver for testing.
+pub(crate) fn resolve(specifier: &str, from: &Path) -> Option<String> {
+    let path = from.parent()?.join(specifier).canonicalize().ok()?;
+    Some(path.to_string_lossy().into_owned())
+}
+
+/// Returns the path of the module in the fixture directory, which may not exist.
+pub(crate) fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(name)
 }
@@ -3,29 +3,55 @@ use std::sync::Arc;
 
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
-use napi::bindgen_prelude::*;
 use oxc::allocator::Allocator;
 use oxc::codegen::{Codegen, CodegenOptions, CodegenReturn};
+use oxc::diagnostics::Severity;
 use oxc_sourcemap::SourceMap;
 use oxc_traverse::traverse_mut;
 
+use crate::diagnostic::{Diagnostic, TransformError};
 use crate::loader::Loader;
-use crate::pass::Transformer;
+use crate::module_graph::ModuleGraph;
+use crate::pass::{Transformer, TransformerOptions};
 
 pub(crate) fn _transform(
     source_text: String,
     source_path: String,
-) -> Result<crate::TransformedSource> {
+    options: &TransformerOptions,
+) -> Result<crate::TransformedSource, TransformError> {
     let source_path = PathBuf::from(source_path);
     let allocator = Allocator::new();
     let (mut program, scoping) = Loader
-        .load_str(&allocator, &source_text, &source_path)
-        .map_err(|_| Error::from_reason("Could not load a source file. Invalid syntax?"))?;
+        .load_str(&allocator, &source_text, &source_path, options.source_type)
+        .map_err(|errors| TransformError::new(errors, &source_text, &source_path))?;
 
-    let mut transformer = Transformer::new();
+    let module_graph = options
+        .resolver
+        .map(|resolver| ModuleGraph::new(resolver, &source_path));
+    let mut transformer = Transformer::new(options, module_graph.as_ref());
 
     traverse_mut(&mut transformer, &allocator, &mut program, scoping, ());
 
+    // Warnings are promoted to errors in the strict mode
+    let (errors, warnings): (Vec<_>, Vec<_>) = transformer
+        .into_diagnostics()
+        .into_iter()
+        .map(|diagnostic| {
+            if options.strict {
+                diagnostic.with_severity(Severity::Error)
+            } else {
+                diagnostic
+            }
+        })
+        .partition(|diagnostic| diagnostic.severity == Severity::Error);
+    if !errors.is_empty() {
+        return Err(TransformError::new(errors, &source_text, &source_path));
+    }
+    let warnings = warnings
+        .into_iter()
+        .map(|warning| Diagnostic::new(warning, &source_text, &source_path))
+        .collect();
+
     let CodegenReturn { mut code, map, .. } = Codegen::new()
         .with_options(CodegenOptions {
             source_map_path: Some(source_path.clone()),
@@ -54,12 +80,25 @@ pub(crate) fn _transform(
     code.push_str("\n//# sourceMappingURL=data:application/json;base64,");
     BASE64_STANDARD.encode_string(map.as_bytes(), &mut code);
 
-    Ok(crate::TransformedSource { code, map })
+    let dependencies = module_graph
+        .iter()
+        .flat_map(ModuleGraph::dependencies)
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+
+    Ok(crate::TransformedSource {
+        code,
+        map,
+        warnings,
+        dependencies,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{fixture_path, resolve};
+    use oxc::span::SourceType;
     use std::path::Path;
 
     fn test(source_path: &Path) {
@@ -70,11 +109,192 @@ mod tests {
             .to_string_lossy()
             .to_string();
 
+        // Source paths are relative to the fixture directory, to keep the snapshots portable
+        let resolver =
+            |specifier: &str, from: &Path| resolve(specifier, &fixture_path("").join(from));
+        let options = TransformerOptions {
+            resolver: Some(&resolver),
+            ..Default::default()
+        };
+
         let crate::TransformedSource { code, .. } =
-            _transform(source_text, source_path.clone()).unwrap();
+            _transform(source_text, source_path.clone(), &options).unwrap();
 
         insta::assert_snapshot!(source_path.as_str(), code);
     }
 
     test_each_file::test_each_path! { in "./tests" => test }
+
+    #[test]
+    fn test_errors() {
+        let source_text = r#"
+const message = "Hello from mocked module!";
+
+jest.mock("./greeter.ts", () => ({
+  greet: () => message,
+}));
+"#;
+
+        let error = _transform(
+            source_text.to_string(),
+            "errors.test.ts".to_string(),
+            &TransformerOptions::default(),
+        )
+        .unwrap_err();
+
+        insta::assert_snapshot!(error.message(), @r#"
+         × The module factory of a hoisted mock is not allowed to reference any out-of-scope variables.
+          ╭─[errors.test.ts:5:16]
+        4 │ jest.mock("./greeter.ts", () => ({
+        5 │   greet: () => message,
+          ·                ───┬───
+          ·                   ╰── Invalid variable access: message
+        6 │ }));
+          ╰────
+         help: Variable names prefixed with `mock` (case insensitive) are permitted, if it is ensured that the mock is imported lazily.
+        "#);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let source_text = r#"
+import { greet } from "./greeter.ts";
+
+const = greet();
+"#;
+
+        let error = _transform(
+            source_text.to_string(),
+            "syntax.test.ts".to_string(),
+            &TransformerOptions::default(),
+        )
+        .unwrap_err();
+
+        insta::assert_debug_snapshot!(error.diagnostics, @r#"
+        [
+            Diagnostic {
+                severity: "error",
+                message: "Unexpected token",
+                help: None,
+                file: "syntax.test.ts",
+                line: 4,
+                column: 7,
+                code_frame: "  × Unexpected token\n   ╭─[syntax.test.ts:4:7]\n 3 │ \n 4 │ const = greet();\n   ·       ─\n   ╰────",
+            },
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let source_text = r#"
+describe("greeter", () => {
+  jest.isolateModules(() => {});
+});
+"#;
+
+        let crate::TransformedSource { warnings, .. } = _transform(
+            source_text.to_string(),
+            "warnings.test.ts".to_string(),
+            &TransformerOptions::default(),
+        )
+        .unwrap();
+
+        insta::assert_debug_snapshot!(warnings, @r#"
+        [
+            Diagnostic {
+                severity: "warning",
+                message: "`jest.isolateModules` can't be awaited here, thus it's left untransformed.",
+                help: Some(
+                    "Call `jest.isolateModules` in a test or a hook, or use `jest.isolateModulesAsync` instead.",
+                ),
+                file: "warnings.test.ts",
+                line: 3,
+                column: 3,
+                code_frame: "  ⚠ `jest.isolateModules` can't be awaited here, thus it's left untransformed.\n   ╭─[warnings.test.ts:3:3]\n 2 │ describe(\"greeter\", () => {\n 3 │   jest.isolateModules(() => {});\n   ·   ──────────────┬──────────────\n   ·                 ╰── Not awaitable\n 4 │ });\n   ╰────\n  help: Call `jest.isolateModules` in a test or a hook, or use `jest.isolateModulesAsync` instead.",
+            },
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_warnings_strict() {
+        let source_text = r#"
+describe("greeter", () => {
+  jest.isolateModules(() => {});
+});
+"#;
+
+        let error = _transform(
+            source_text.to_string(),
+            "warnings.test.ts".to_string(),
+            &TransformerOptions {
+                strict: true,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+
+        insta::assert_snapshot!(error.message(), @r#"
+         × `jest.isolateModules` can't be awaited here, thus it's left untransformed.
+          ╭─[warnings.test.ts:3:3]
+        2 │ describe("greeter", () => {
+        3 │   jest.isolateModules(() => {});
+          ·   ──────────────┬──────────────
+          ·                 ╰── Not awaitable
+        4 │ });
+          ╰────
+         help: Call `jest.isolateModules` in a test or a hook, or use `jest.isolateModulesAsync` instead.
+        "#);
+    }
+
+    #[test]
+    fn test_unknown_extension() {
+        let error = _transform(
+            "export const answer = 42;".to_string(),
+            "answer.es6".to_string(),
+            &TransformerOptions::default(),
+        )
+        .unwrap_err();
+
+        insta::assert_snapshot!(error.message(), @r#"
+        × Cannot infer the source type of `answer.es6` from its extension.
+        help: Specify `sourceType` option, such as `"js"` or `"ts"`, for the modules with unknown extensions.
+        "#);
+    }
+
+    #[test]
+    fn test_source_type() {
+        let crate::TransformedSource { code, .. } = _transform(
+            "export const answer: number = 42;".to_string(),
+            "answer.es6".to_string(),
+            &TransformerOptions {
+                source_type: Some(SourceType::ts()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Type annotations are stripped as TypeScript
+        let (code, _) = code.split_once("\n//# sourceMappingURL").unwrap();
+        insta::assert_snapshot!(code, @r#"
+        import * as __oxjest__ from "oxjest/runtime";
+        export const answer = 42;
+        "#);
+    }
+
+    #[test]
+    fn test_source_type_known_extension() {
+        let result = _transform(
+            "export const answer: number = 42;".to_string(),
+            "answer.js".to_string(),
+            &TransformerOptions {
+                source_type: Some(SourceType::ts()),
+                ..Default::default()
+            },
+        );
+
+        // Modules with known extensions are parsed as the languages of the extensions
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,15 @@
+import { count } from "./counter.ts";
+import { greet } from "./greeter.ts";
+
+jest
+  .mock("./greeter.ts", () => ({
+    greet: () => "Hello from mocked module!",
+  }))
+  .mock("./counter.ts", () => ({
+    count: 42,
+  }));
+
+test("chained mocks are hoisted one by one", () => {
+  expect(greet()).toBe("Hello from mocked module!");
+  expect(count).toBe(42);
+});
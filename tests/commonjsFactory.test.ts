@@ -0,0 +1,18 @@
+import { count } from "./counter.ts";
+import greet from "./defaultGreeter.ts";
+
+jest.mock("./defaultGreeter.ts", () => jest.fn(() => "Hello from mocked module!"));
+
+jest.mock("./counter.ts", () => ({
+  __esModule: true,
+  count: 42,
+}));
+
+test("factories returning functions are mocked as the default exports", () => {
+  expect(greet()).toBe("Hello from mocked module!");
+  expect(jest.isMockFunction(greet)).toBe(true);
+});
+
+test("factories returning `__esModule` objects are mocked as the namespaces", () => {
+  expect(count).toBe(42);
+});
@@ -0,0 +1,5 @@
+const greeting = process.env.GREETING ?? "Hello from CommonJS!";
+
+module.exports = function greet() {
+  return greeting;
+};
@@ -0,0 +1,12 @@
+import { greet } from "./greeter.ts";
+
+const GREETER = "./greeter.ts";
+
+jest.mock(GREETER, () => ({
+  greet: () => "Hello from mocked module!",
+}));
+
+test("const-bound specifiers are inlined into the hoisted mocks", () => {
+  expect(greet()).toBe("Hello from mocked module!");
+  expect(jest.requireActual(`./greeter.ts`).greet()).toBe("Hello, world!");
+});
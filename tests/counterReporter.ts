@@ -0,0 +1,5 @@
+import { count } from "./counter.ts";
+
+export function report(): string {
+  return `count: ${count}`;
+}
@@ -0,0 +1,26 @@
+import { count } from "./counter.ts";
+
+jest.mock("./greeter.ts", () => ({
+  greet: jest.fn(() => "Hello from mocked module!"),
+}));
+
+describe("callbacks taking done", () => {
+  it("requires the mocked module", (done) => {
+    const greeter = jest.requireMock<typeof import("./greeter.ts")>("./greeter.ts");
+
+    expect(greeter.greet()).toBe("Hello from mocked module!");
+    done();
+  });
+
+  it("isolates modules without awaiting them", (done) => {
+    jest.isolateModules(() => {
+      const isolated = require("./counter.ts");
+
+      isolated.increment();
+      expect(isolated.count).toBe(1);
+    });
+
+    expect(count).toBe(0);
+    done();
+  });
+});
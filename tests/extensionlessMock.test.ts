@@ -0,0 +1,9 @@
+import { greet } from "./greeter.ts";
+
+jest.mock("./greeter", () => ({
+  greet: () => "Hello from mocked module!",
+}));
+
+test("mocks are resolved with the module file extensions", () => {
+  expect(greet()).toBe("Hello from mocked module!");
+});
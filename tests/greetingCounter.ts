@@ -0,0 +1,11 @@
+import { greet } from "./greeter.ts";
+
+export let count = 0;
+
+export function increment(): void {
+  count += 1;
+}
+
+export function greetCount(): string {
+  return `${greet()} (${count})`;
+}
@@ -0,0 +1,25 @@
+import { count, increment } from "./counter.ts";
+
+describe("isolateModules", () => {
+  it("imports an isolated module", () => {
+    let isolated: typeof import("./counter.ts");
+    jest.isolateModules(() => {
+      isolated = require("./counter.ts");
+    });
+
+    increment();
+
+    expect(count).toBe(1);
+    expect(isolated!.count).toBe(0);
+  });
+
+  it("imports an isolated module asynchronously", async () => {
+    await jest.isolateModulesAsync(async () => {
+      const isolated = require("./counter.ts");
+
+      isolated.increment();
+
+      expect(isolated.count).toBe(1);
+    });
+  });
+});
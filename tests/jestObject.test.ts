@@ -0,0 +1,19 @@
+function createSpy(jestObject: typeof jest): jest.Mock {
+  return jestObject.fn();
+}
+
+describe("jest object", () => {
+  it("can be destructured", () => {
+    const { fn, isMockFunction } = jest;
+
+    expect(isMockFunction(fn())).toBe(true);
+  });
+
+  it("can be passed as an argument", () => {
+    expect(jest.isMockFunction(createSpy(jest))).toBe(true);
+  });
+
+  it("can be guarded by typeof", () => {
+    expect(typeof jest !== "undefined").toBe(true);
+  });
+});
@@ -0,0 +1,4 @@
+export async function lazyGreet(): Promise<string> {
+  const { default: greet } = await import("./defaultGreeter.ts");
+  return greet();
+}
@@ -0,0 +1,3 @@
+export function load(name: string): Promise<unknown> {
+  return import(`./${name}.ts`);
+}
@@ -0,0 +1,9 @@
+import { lazyGreet } from "./lazyGreeter.ts";
+
+jest.mock("./defaultGreeter.ts", () => ({
+  default: () => "Hello from mocked module!",
+}));
+
+test("modules imported lazily are mocked", async () => {
+  expect(await lazyGreet()).toBe("Hello from mocked module!");
+});
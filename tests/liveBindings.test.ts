@@ -0,0 +1,9 @@
+import { count, increment } from "./counter.ts";
+
+jest.mock("./greeter.ts");
+
+test("imported bindings reflect the current values", () => {
+  expect(count).toBe(0);
+  increment();
+  expect(count).toBe(1);
+});
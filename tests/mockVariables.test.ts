@@ -0,0 +1,13 @@
+import { greet } from "./greeter.ts";
+
+const mockMessage = "Hello from mock variables!";
+const mockGreet = jest.fn().mockReturnValue(mockMessage);
+
+jest.mock("./greeter.ts", () => ({
+  greet: mockGreet,
+}));
+
+test("mock variables are initialized before the mock", () => {
+  expect(greet()).toBe(mockMessage);
+  expect(mockGreet).toHaveBeenCalled();
+});
@@ -0,0 +1,11 @@
+import { report } from "./counterReporter.ts";
+import { greet } from "./greeter.ts";
+
+jest.mock("./counter.ts", () => ({
+  count: 42,
+}));
+
+test("only the imports reaching the mocked modules are converted", () => {
+  expect(report()).toBe("count: 42");
+  expect(greet()).toBe("Hello, world!");
+});
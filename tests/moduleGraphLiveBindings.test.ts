@@ -0,0 +1,12 @@
+import { count, greetCount, increment } from "./greetingCounter.ts";
+
+jest.mock("./greeter.ts", () => ({
+  greet: () => "Hello from mocked module!",
+}));
+
+test("bindings imported from the modules reaching the mocks reflect the current values", () => {
+  expect(count).toBe(0);
+  increment();
+  expect(count).toBe(1);
+  expect(greetCount()).toBe("Hello from mocked module! (1)");
+});
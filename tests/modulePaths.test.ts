@@ -0,0 +1,15 @@
+import { basename, join } from "node:path";
+
+const fixture = join(__dirname, "greeter.ts");
+
+describe("module paths", () => {
+  it("polyfills __dirname and __filename", () => {
+    expect(basename(__filename)).toBe("modulePaths.test.ts");
+    expect(fixture).toBe(join(import.meta.dirname, "greeter.ts"));
+  });
+
+  it("polyfills import.meta.dirname and import.meta.filename", () => {
+    expect(import.meta.filename).toBe(__filename);
+    expect(import.meta.dirname).toBe(__dirname);
+  });
+});
@@ -0,0 +1,12 @@
+import { ping, pingPong } from "./ping.ts";
+import { pong, pongPing } from "./pong.ts";
+
+jest.mock("./ping.ts");
+jest.mock("./pong.ts");
+
+test("both modules are mocked automatically", () => {
+  expect(jest.isMockFunction(ping)).toBe(true);
+  expect(jest.isMockFunction(pingPong)).toBe(true);
+  expect(jest.isMockFunction(pong)).toBe(true);
+  expect(jest.isMockFunction(pongPing)).toBe(true);
+});
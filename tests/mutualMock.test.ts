@@ -0,0 +1,17 @@
+import { ping, pingPong } from "./ping.ts";
+import { pong } from "./pong.ts";
+
+jest.mock("./ping.ts", () => ({
+  ...jest.requireActual("./ping.ts"),
+  ping: () => "mocked ping",
+}));
+jest.mock("./pong.ts", () => ({
+  ...jest.requireActual("./pong.ts"),
+  pong: () => "mocked pong",
+}));
+
+test("the actual modules import the other modules mocked", () => {
+  expect(ping()).toBe("mocked ping");
+  expect(pong()).toBe("mocked pong");
+  expect(pingPong()).toBe("ping mocked pong");
+});
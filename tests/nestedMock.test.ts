@@ -0,0 +1,13 @@
+describe("nested mock", () => {
+  const mockMessage = "Hello from nested mock!";
+
+  it("mock a module inside a test", async () => {
+    const { greet } = await import("./greeter.ts");
+
+    jest.mock("./greeter.ts", () => ({
+      greet: jest.fn(() => mockMessage),
+    }));
+
+    expect(greet()).toBe(mockMessage);
+  });
+});
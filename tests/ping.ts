@@ -0,0 +1,9 @@
+import { pong } from "./pong.ts";
+
+export function ping(): string {
+  return "ping";
+}
+
+export function pingPong(): string {
+  return `${ping()} ${pong()}`;
+}
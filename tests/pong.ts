@@ -0,0 +1,9 @@
+import { ping } from "./ping.ts";
+
+export function pong(): string {
+  return "pong";
+}
+
+export function pongPing(): string {
+  return `${pong()} ${ping()}`;
+}
@@ -0,0 +1,22 @@
+import { greet } from "./greeter.ts";
+
+export { greet as mockedGreet } from "./greeter.ts";
+export * as counter from "./counter.ts";
+export * from "./greeter.ts";
+
+jest.mock("./greeter.ts", () => ({
+  greet: jest.fn(() => "Hello from mocked module!"),
+}));
+
+test("re-exports are evaluated after the mocks", async () => {
+  const { mockedGreet } = await import("./reexports.test.ts");
+
+  expect(mockedGreet).toBe(greet);
+  expect(mockedGreet()).toBe("Hello from mocked module!");
+});
+
+test("names of `export * from` are re-exported after the mocks", async () => {
+  const { greet: reexportedGreet } = await import("./reexports.test.ts");
+
+  expect(reexportedGreet).toBe(greet);
+});
@@ -0,0 +1,24 @@
+const { greet } = require("./greeter.ts");
+
+jest.mock("./greeter.ts", () => ({
+  greet: jest.fn(() => "Hello from mocked module!"),
+}));
+
+process.env.GREETING = "Hello from environment!";
+const greetCommonJs = require("./commonjsGreeter.cjs");
+
+describe("require", () => {
+  it("requires the mocked module", () => {
+    expect(greet()).toBe("Hello from mocked module!");
+  });
+
+  it("requires the mocked module in a test", () => {
+    const greeter = require("./greeter.ts");
+
+    expect(greeter.greet()).toBe("Hello from mocked module!");
+  });
+
+  it("requires CommonJS modules as their exports, after the preceding statements", () => {
+    expect(greetCommonJs()).toBe("Hello from environment!");
+  });
+});
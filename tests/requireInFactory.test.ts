@@ -0,0 +1,14 @@
+import { greet } from "./greeter.ts";
+
+jest.mock("./greeter.ts", () => ({
+  greet: require("./commonjsGreeter.cjs"),
+}));
+
+const actual = jest.requireActual<typeof import("./greeter.ts")>("./greeter.ts");
+const mocked = jest.requireMock<typeof import("./greeter.ts")>("./greeter.ts");
+
+test("mock factories require modules before the hoisted imports", () => {
+  expect(greet()).toBe("Hello from CommonJS!");
+  expect(mocked.greet).toBe(greet);
+  expect(actual.greet()).toBe("Hello, world!");
+});
@@ -0,0 +1,25 @@
+import { greet } from "./greeter.ts";
+
+jest.mock("./greeter.ts", () => ({
+  greet: jest.fn(() => "Hello from mocked module!"),
+}));
+
+describe("requireMock", () => {
+  it("get the mocked module", () => {
+    const greeter = jest.requireMock("./greeter.ts");
+
+    expect(greeter.greet).toBe(greet);
+    expect(greeter.greet()).toBe("Hello from mocked module!");
+  });
+
+  it("gets the mock registered after resetting the modules", () => {
+    jest.resetModules();
+    jest.doMock("./greeter.ts", () => ({
+      greet: () => "Hello from another mocked module!",
+    }));
+
+    const greeter = jest.requireMock("./greeter.ts");
+
+    expect(greeter.greet()).toBe("Hello from another mocked module!");
+  });
+});
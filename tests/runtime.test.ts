@@ -1,15 +1,15 @@
 import { jest } from "@jest/globals";
-import { createMockFactory } from "oxjest/runtime";
+import { createMockFactory, importActual, mockModule, normalizeFactory } from "oxjest/runtime";
 
 describe("createMockFactory", () => {
   it("create a mock for function", async () => {
-    const factory = createMockFactory({
+    const factory = createMockFactory(jest, "./greeter.ts", async () => ({
       greet(): string {
         return "Hello, world!";
       },
-    });
+    }));
 
-    const mock = factory();
+    const mock = await factory();
 
     expect(jest.isMockFunction(mock.greet)).toBe(true);
   });
@@ -21,9 +21,57 @@ describe("createMockFactory", () => {
       }
     }
 
-    const factory = createMockFactory({ Greeter });
-    const mock = factory();
+    const factory = createMockFactory(jest, "./greeter.ts", async () => ({ Greeter }));
+    const mock = await factory();
 
     expect(jest.isMockFunction(mock.Greeter.greet)).toBe(true);
   });
 });
+
+describe("normalizeFactory", () => {
+  it("maps a function into the default export", () => {
+    const greet = jest.fn();
+    const factory = normalizeFactory(() => greet);
+
+    expect(factory()).toEqual({ default: greet });
+  });
+
+  it("maps a primitive into the default export", () => {
+    const factory = normalizeFactory(() => "Hello, world!");
+
+    expect(factory()).toEqual({ default: "Hello, world!" });
+  });
+
+  it("removes the `__esModule` flag", () => {
+    const greet = jest.fn();
+    const factory = normalizeFactory(() => ({ __esModule: true, default: greet, name: "greeter" }));
+
+    expect(factory()).toEqual({ default: greet, name: "greeter" });
+  });
+
+  it("keeps an object as it is", () => {
+    const module = { greet: jest.fn() };
+    const factory = normalizeFactory(() => module);
+
+    expect(factory()).toBe(module);
+  });
+
+  it("normalizes the value resolved by an async factory", async () => {
+    const greet = jest.fn();
+    const factory = normalizeFactory(async () => greet);
+
+    await expect(factory()).resolves.toEqual({ default: greet });
+  });
+});
+
+describe("importActual", () => {
+  it("imports the actual module mocked through the runtime", async () => {
+    mockModule(jest, "./greeter.ts", () => ({ greet: jest.fn() }));
+
+    const actual = await importActual(jest, "./greeter.ts", () => import("./greeter.ts"));
+    const mocked = await import("./greeter.ts");
+
+    expect(actual.greet()).toBe("Hello, world!");
+    expect(jest.isMockFunction(mocked.greet)).toBe(true);
+  });
+});
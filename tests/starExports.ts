@@ -0,0 +1,3 @@
+export * from "./greeter.ts";
+export * as counter from "./counter.ts";
+export { default } from "./defaultGreeter.ts";
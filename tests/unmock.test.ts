@@ -0,0 +1,22 @@
+import { greet } from "./greeter.ts";
+
+jest.mock("./greeter.ts");
+jest.unmock("./greeter.ts");
+
+describe("unmock", () => {
+  it("use the actual module", () => {
+    expect(greet()).toBe("Hello, world!");
+  });
+
+  it("use the actual module after dontMock", async () => {
+    jest.resetModules();
+    jest.doMock("./greeter.ts", () => ({
+      greet: jest.fn(() => "Hello from mocked module!"),
+    }));
+    jest.dontMock("./greeter.ts");
+
+    const { greet } = await import("./greeter.ts");
+
+    expect(greet()).toBe("Hello, world!");
+  });
+});
@@ -0,0 +1,11 @@
+import { greet } from "./greeter.ts";
+
+jest.unstable_mockModule("./greeter.ts", () => ({
+  greet: jest.fn(() => "Hello from mocked module!"),
+}));
+
+describe("unstable_mockModule", () => {
+  it("mock a module", () => {
+    expect(greet()).toBe("Hello from mocked module!");
+  });
+});
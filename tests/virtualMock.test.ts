@@ -0,0 +1,16 @@
+// @ts-expect-error: the module does not exist on disk
+import { greet } from "virtual-greeter";
+
+jest.mock(
+  "virtual-greeter",
+  () => ({
+    greet: jest.fn(() => "Hello from virtual module!"),
+  }),
+  { virtual: true },
+);
+
+describe("virtual mock", () => {
+  it("mock a module that does not exist", () => {
+    expect(greet()).toBe("Hello from virtual module!");
+  });
+});